@@ -1,5 +1,40 @@
 use clap::Parser;
-use mktoc::Config;
+use mktoc::{Bullet, Config, Diagnostic, Error, Indent, Numbering, SlugStyle, Template};
+use std::fmt;
+use std::str::FromStr;
+
+/// What mktoc outputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// the file with the updated ToC
+    Markdown,
+    /// the outline of the file with the effective config as JSON
+    Json,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Markdown => f.write_str("markdown"),
+            Format::Json => f.write_str("json"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "unknown format '{}', expected one of: markdown, json",
+                s
+            )),
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -11,6 +46,10 @@ struct Args {
     #[arg(long, short)]
     stdout: bool,
 
+    /// Output format: markdown updates the file, json prints the outline and the config to stdout
+    #[arg(long, short = 'f', default_value_t = Format::Markdown)]
+    format: Format,
+
     /// Minimum heading level
     #[arg(long, short = 'm', default_value_t = 1, env = "MKTOC_MIN_DEPTH")]
     min_depth: i32,
@@ -20,8 +59,65 @@ struct Args {
     max_depth: i32,
 
     /// Wrap ToC in details html element
-    #[arg(long, short = 'w', default_value_t = false, env = "MKTOC_WRAP_IN_DETAILS")]
+    #[arg(
+        long,
+        short = 'w',
+        default_value_t = false,
+        env = "MKTOC_WRAP_IN_DETAILS"
+    )]
     wrap_in_details: bool,
+
+    /// Platform whose rules are used to create anchors, e.g. github, gitlab or mdbook
+    #[arg(long, default_value_t = SlugStyle::GitHub, env = "MKTOC_SLUG_STYLE")]
+    slug_style: SlugStyle,
+
+    /// Remove inline code and emphasis from the ToC entries
+    #[arg(long, default_value_t = false, env = "MKTOC_STRIP_FORMATTING")]
+    strip_formatting: bool,
+
+    /// Leave headings with this text out of the ToC, /regex/ matches a regex instead
+    #[arg(long, short = 'x', value_name = "TEXT")]
+    exclude: Vec<String>,
+
+    /// Indent entries by their absolute heading level like mktoc up to version 5
+    #[arg(long, default_value_t = false, env = "MKTOC_LEGACY_INDENT")]
+    legacy_indent: bool,
+
+    /// Marker of the ToC entries: -, * or +
+    #[arg(long, default_value_t = Bullet::Dash, env = "MKTOC_BULLET")]
+    bullet: Bullet,
+
+    /// Render the ToC as ordered list
+    #[arg(long, default_value_t = false, env = "MKTOC_ORDERED")]
+    ordered: bool,
+
+    /// Indentation of nested entries, a number of spaces or tab (2 by default, the width of the parent marker for ordered lists)
+    #[arg(long, env = "MKTOC_INDENT")]
+    indent: Option<Indent>,
+
+    /// Prefix entries with hierarchical numbers in the given format: 1.2., 1.2, A.1 or I.1
+    #[arg(long, env = "MKTOC_NUMBERING")]
+    numbering: Option<Numbering>,
+
+    /// Leave top level entries unnumbered and restart numbering below each of them
+    #[arg(long, default_value_t = false, env = "MKTOC_RESTART_NUMBERING")]
+    restart_numbering: bool,
+
+    /// Write the numbers into the headings of the document, implies --numbering 1.2. if not set
+    #[arg(long, default_value_t = false, env = "MKTOC_RENUMBER_HEADINGS")]
+    renumber_headings: bool,
+
+    /// Render the ToC as nested HTML lists
+    #[arg(long, default_value_t = false, env = "MKTOC_HTML")]
+    html: bool,
+
+    /// Fail on unknown keys in the inline config instead of warning about them
+    #[arg(long, default_value_t = false, env = "MKTOC_STRICT")]
+    strict: bool,
+
+    /// Template file with the header, entry line and footer to render the ToC with
+    #[arg(long, short = 't', value_name = "FILE", env = "MKTOC_TEMPLATE")]
+    template: Option<String>,
 }
 
 fn handle_write(args: Args, new_toc: String) {
@@ -41,19 +137,81 @@ fn handle_write(args: Args, new_toc: String) {
     }
 }
 
+/// returns the exit code for the error, 2 is used by clap for invalid arguments
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::Io(_) => 1,
+        Error::MissingMarkers | Error::UnbalancedMarkers { .. } => 3,
+        Error::InvalidConfig { .. } => 4,
+        Error::DepthOutOfRange { .. } | Error::InvertedDepths { .. } => 5,
+        Error::InvalidUtf8 { .. } => 6,
+    }
+}
+
+/// prints the warnings to stderr, so they don't end up in the ToC written to stdout
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+}
+
+fn print_outline(file: &str, config: Config) -> ! {
+    match mktoc::make_outline(file, config) {
+        Ok(outline) => {
+            print_diagnostics(&outline.diagnostics);
+            println!("{}", serde_json::to_string_pretty(&outline).unwrap());
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(exit_code(&e));
+        }
+    }
+}
+
 fn main() {
     let args = Args::parse();
-    let config = Config{min_depth: args.min_depth, max_depth: args.max_depth, wrap_in_details: args.wrap_in_details, ..Default::default()};
-    let res = mktoc::make_toc(&args.file, config);
+    let template = match args.template.as_ref().map(Template::from_file).transpose() {
+        Ok(template) => template,
+        Err(e) => {
+            eprintln!("Failed to read template file. Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let config = Config {
+        min_depth: args.min_depth,
+        max_depth: args.max_depth,
+        wrap_in_details: args.wrap_in_details,
+        slug_style: args.slug_style,
+        strip_formatting: args.strip_formatting,
+        exclude: args.exclude.clone(),
+        legacy_indent: args.legacy_indent,
+        bullet: args.bullet,
+        ordered: args.ordered,
+        indent: args.indent,
+        numbering: args.numbering,
+        restart_numbering: args.restart_numbering,
+        renumber_headings: args.renumber_headings,
+        html: args.html,
+        template,
+        strict: args.strict,
+        ..Default::default()
+    };
+    if args.format == Format::Json {
+        print_outline(&args.file, config);
+    }
+
+    let res = mktoc::make_toc_with_diagnostics(&args.file, config);
 
     match res {
-        Ok(new_toc) => {
+        Ok((new_toc, diagnostics)) => {
+            print_diagnostics(&diagnostics);
             handle_write(args, new_toc);
             std::process::exit(0);
         }
         Err(e) => {
             eprintln!("Error: {}", e);
-            std::process::exit(1);
+            std::process::exit(exit_code(&e));
         }
     };
 }
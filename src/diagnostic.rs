@@ -0,0 +1,63 @@
+//! Warnings about a document or its config.
+//!
+//! The library doesn't print anything, problems that don't stop the ToC from being
+//! generated are returned as [`Diagnostic`]s and it's up to the caller to show them.
+
+use serde::Serialize;
+use std::fmt;
+
+/// How serious a [`Diagnostic`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// something that is probably not intended, like a skipped heading level
+    Info,
+    /// a part of the document or config that is ignored
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => f.write_str("INFO"),
+            Severity::Warning => f.write_str("WARNING"),
+        }
+    }
+}
+
+/// A problem found while generating the ToC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// line in the document the problem was found at, counted from 1. Not set for
+    /// problems with a config that is not inline.
+    pub line: Option<usize>,
+}
+
+impl Diagnostic {
+    pub(crate) fn warning(message: String, line: Option<usize>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+            line,
+        }
+    }
+
+    pub(crate) fn info(message: String, line: Option<usize>) -> Self {
+        Self {
+            severity: Severity::Info,
+            message,
+            line,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}: line {}: {}", self.severity, line, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! The errors mktoc can fail with.
+
+use std::fmt;
+use std::io;
+
+/// Errors returned by [`make_toc`](crate::make_toc) and [`make_outline`](crate::make_outline)
+#[derive(Debug)]
+pub enum Error {
+    /// a file could not be read or written
+    Io(io::Error),
+    /// the file is not valid UTF-8, `line` is the line of the first invalid byte
+    InvalidUtf8 { line: usize },
+    /// the file has neither a `<!-- BEGIN mktoc -->` nor a `<!-- END mktoc -->` marker
+    MissingMarkers,
+    /// a begin marker without end marker after it, or an end marker without begin
+    /// marker before it, in the given line
+    UnbalancedMarkers { line: usize },
+    /// the JSON of the inline config can't be parsed, has a value of the wrong type or,
+    /// with `strict`, an unknown key. `line` and `column` are the position of the error
+    /// in the file
+    InvalidConfig {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// `min_depth` or `max_depth` is not within 1 and 6
+    DepthOutOfRange { name: &'static str, value: i32 },
+    /// `min_depth` is greater than `max_depth`, so no heading would be in the ToC
+    InvertedDepths { min_depth: i32, max_depth: i32 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::InvalidUtf8 { line } => write!(f, "invalid UTF-8 in line {}", line),
+            Error::MissingMarkers => write!(
+                f,
+                "no <!-- BEGIN mktoc --> and <!-- END mktoc --> markers found"
+            ),
+            Error::UnbalancedMarkers { line } => write!(
+                f,
+                "unbalanced marker in line {}, expected <!-- BEGIN mktoc --> followed by <!-- END mktoc -->",
+                line
+            ),
+            Error::InvalidConfig {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "invalid inline config in line {} column {}: {}",
+                line, column, message
+            ),
+            Error::DepthOutOfRange { name, value } => {
+                write!(f, "{} of {} out of bound, expected 1 to 6", name, value)
+            }
+            Error::InvertedDepths {
+                min_depth,
+                max_depth,
+            } => write!(
+                f,
+                "min_depth of {} is greater than max_depth of {}",
+                min_depth, max_depth
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
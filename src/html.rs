@@ -0,0 +1,105 @@
+//! Renders the ToC as nested HTML lists.
+//!
+//! Markdown lists turn into code blocks when they are indented inside HTML elements
+//! like `<details>` or `<nav>`, and static sites that embed the ToC in their own
+//! HTML need markup anyway. The entries are rendered as plain text, escaped and
+//! linked to their anchors, with a nested `<ul>` or `<ol>` for every level.
+
+use crate::{Config, TocNode, entry_text, inline};
+use std::collections::HashSet;
+
+/// renders the entries as nested lists, one element per line
+///
+/// Entries that skip a level get a list item without link as parent, so the lists
+/// are always nested in a list item as the HTML spec requires.
+pub(crate) fn render(entries: &[&TocNode], config: &Config) -> String {
+    let tag = if config.ordered { "ol" } else { "ul" };
+    let mut lines: Vec<String> = Vec::new();
+    // number of open lists, all but the innermost one in an open list item
+    let mut open = 0;
+    let mut previous_depth = None;
+
+    for (i, entry) in entries.iter().enumerate() {
+        while open > entry.depth + 1 {
+            open -= 1;
+            lines.push(format!("{}</{}>", indent(open, 0), tag));
+            lines.push(format!("{}</li>", indent(open - 1, 1)));
+        }
+        while open < entry.depth + 1 {
+            // a skipped level has no list item of its own to nest the list in
+            if open > 0 && previous_depth != Some(open - 1) {
+                lines.push(format!("{}<li>", indent(open - 1, 1)));
+            }
+            lines.push(format!("{}<{}>", indent(open, 0), tag));
+            open += 1;
+        }
+
+        let mut item = format!(
+            "{}<li><a href=\"#{}\">{}</a>",
+            indent(entry.depth, 1),
+            escape(&entry.heading.anchor),
+            text(entry, config)
+        );
+        // the item stays open for the nested list of the next entry
+        if entries
+            .get(i + 1)
+            .is_none_or(|next| next.depth <= entry.depth)
+        {
+            item.push_str("</li>");
+        }
+        lines.push(item);
+        previous_depth = Some(entry.depth);
+    }
+
+    while open > 0 {
+        open -= 1;
+        lines.push(format!("{}</{}>", indent(open, 0), tag));
+        if open > 0 {
+            lines.push(format!("{}</li>", indent(open - 1, 1)));
+        }
+    }
+
+    lines.iter().map(|line| format!("\n{}", line)).collect()
+}
+
+/// returns the text of the entry as escaped plain text
+pub(crate) fn text(entry: &TocNode, config: &Config) -> String {
+    escape(&inline::render(&entry_text(entry, config), &HashSet::new()).plain)
+}
+
+/// returns the indentation of a list of the given depth, or of its items
+fn indent(depth: usize, item: usize) -> String {
+    " ".repeat(4 * depth + 2 * item)
+}
+
+/// escapes text for HTML content and attribute values
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Tom & Jerry"), "Tom &amp; Jerry");
+        assert_eq!(
+            escape(r#"<a href="x">'y'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&#39;y&#39;&lt;/a&gt;"
+        );
+        assert_eq!(escape("Überschrift"), "Überschrift");
+    }
+}
@@ -0,0 +1,681 @@
+//! Rendering of the inline content of headings.
+//!
+//! A heading like `## **New** ![badge](x.svg) <sup>beta</sup>` shows up as
+//! "New beta" in the rendered document, so that is the text the anchor has to be
+//! created from. The ToC entry itself keeps some of the formatting instead.
+
+use crate::{entities, parser};
+use std::collections::HashSet;
+
+/// The inline content of a heading in the forms mktoc needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Inline {
+    /// Markdown used as ToC entry. Inline code and emphasis are kept, links are
+    /// replaced by their text, images and HTML are removed.
+    pub(crate) display: String,
+    /// The text as it shows up in the rendered document, used to create the anchor
+    pub(crate) plain: String,
+}
+
+/// A piece of the heading in both forms. Delimiter runs (`*`, `_` and `~`) can
+/// only be rendered once it is known which of them form emphasis.
+enum Token {
+    Text {
+        display: String,
+        plain: String,
+    },
+    Delimiter {
+        ch: char,
+        len: usize,
+        can_open: bool,
+        can_close: bool,
+        /// number of characters used by matched emphasis
+        used: usize,
+    },
+}
+
+/// renders the inline Markdown of a heading, `link_definitions` are the normalized
+/// labels of the link reference definitions in the document
+pub(crate) fn render(text: &str, link_definitions: &HashSet<String>) -> Inline {
+    let mut tokens = tokenize(text, link_definitions);
+    match_emphasis(&mut tokens);
+
+    let mut display = String::new();
+    let mut plain = String::new();
+    for token in tokens {
+        match token {
+            Token::Text {
+                display: d,
+                plain: p,
+            } => {
+                display.push_str(&d);
+                plain.push_str(&p);
+            }
+            Token::Delimiter { ch, len, used, .. } => {
+                display.extend(std::iter::repeat_n(ch, len));
+                plain.extend(std::iter::repeat_n(ch, len - used));
+            }
+        }
+    }
+
+    Inline {
+        display: display.split_whitespace().collect::<Vec<_>>().join(" "),
+        plain,
+    }
+}
+
+/// appends text to the last token or starts a new one
+fn push_text(tokens: &mut Vec<Token>, display: &str, plain: &str) {
+    if let Some(Token::Text {
+        display: d,
+        plain: p,
+    }) = tokens.last_mut()
+    {
+        d.push_str(display);
+        p.push_str(plain);
+    } else {
+        tokens.push(Token::Text {
+            display: display.to_string(),
+            plain: plain.to_string(),
+        });
+    }
+}
+
+fn tokenize(text: &str, link_definitions: &HashSet<String>) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(c) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+        match c {
+            '\\' => {
+                if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                    push_text(&mut tokens, &rest[..2], &rest[1..2]);
+                    pos += 1 + escaped.len_utf8();
+                    continue;
+                }
+            }
+            '`' => {
+                let len = rest.chars().take_while(|&c| c == '`').count();
+                if let Some(end) = closing_backticks(&rest[len..], len) {
+                    let code = code_span_content(&rest[len..len + end]);
+                    push_text(&mut tokens, &rest[..2 * len + end], &code);
+                    pos += 2 * len + end;
+                } else {
+                    // a backtick string without a matching one is literal text
+                    push_text(&mut tokens, &rest[..len], &rest[..len]);
+                    pos += len;
+                }
+                continue;
+            }
+            '*' | '_' | '~' => {
+                let len = rest.chars().take_while(|&ch| ch == c).count();
+                let before = text[..pos].chars().next_back();
+                let after = rest[len..].chars().next();
+                let (can_open, can_close) = flanking(c, before, after);
+                tokens.push(Token::Delimiter {
+                    ch: c,
+                    len,
+                    can_open,
+                    can_close,
+                    used: 0,
+                });
+                pos += len;
+                continue;
+            }
+            '!' if rest.starts_with("![") => {
+                if let Some((_, len)) = link(&rest[1..], link_definitions) {
+                    pos += 1 + len;
+                    continue;
+                }
+            }
+            '[' => {
+                // footnote references don't show up in the heading
+                if rest.starts_with("[^")
+                    && let Some(end) = rest.find(']')
+                    && end > 2
+                    && !rest[2..end].contains(char::is_whitespace)
+                {
+                    pos += end + 1;
+                    continue;
+                }
+                if let Some((link_text, len)) = link(rest, link_definitions) {
+                    let inner = render(link_text, link_definitions);
+                    push_text(&mut tokens, &inner.display, &inner.plain);
+                    pos += len;
+                    continue;
+                }
+            }
+            '<' => {
+                if let Some(len) = autolink(rest) {
+                    let url = &rest[1..len - 1];
+                    push_text(&mut tokens, url, url);
+                    pos += len;
+                    continue;
+                }
+                if let Some(len) = inline_html(rest) {
+                    pos += len;
+                    continue;
+                }
+            }
+            '&' => {
+                if let Some((decoded, len)) = entity(rest) {
+                    push_text(&mut tokens, &rest[..len], &decoded);
+                    pos += len;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        let len = c.len_utf8();
+        push_text(&mut tokens, &rest[..len], &rest[..len]);
+        pos += len;
+    }
+
+    tokens
+}
+
+/// returns the offset of the backtick string of length `len` closing a code span
+fn closing_backticks(text: &str, len: usize) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(start) = text[pos..].find('`') {
+        let start = pos + start;
+        let run = text[start..].chars().take_while(|&c| c == '`').count();
+        if run == len {
+            return Some(start);
+        }
+        pos = start + run;
+    }
+    None
+}
+
+/// strips one space from both sides of a code span unless it consists of spaces only
+fn code_span_content(code: &str) -> String {
+    let code = code.replace('\n', " ");
+    if code.len() > 1 && code.starts_with(' ') && code.ends_with(' ') && code.trim() != "" {
+        code[1..code.len() - 1].to_string()
+    } else {
+        code
+    }
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+/// returns whether a delimiter run can open and close emphasis (CommonMark 6.2)
+fn flanking(ch: char, before: Option<char>, after: Option<char>) -> (bool, bool) {
+    // the start and the end of the line count as whitespace
+    let before = before.unwrap_or(' ');
+    let after = after.unwrap_or(' ');
+
+    let left = !after.is_whitespace()
+        && (!is_punctuation(after) || before.is_whitespace() || is_punctuation(before));
+    let right = !before.is_whitespace()
+        && (!is_punctuation(before) || after.is_whitespace() || is_punctuation(after));
+
+    if ch == '_' {
+        // `_` doesn't work inside of words like in snake_case
+        (
+            left && (!right || is_punctuation(before)),
+            right && (!left || is_punctuation(after)),
+        )
+    } else {
+        (left, right)
+    }
+}
+
+/// pairs the delimiter runs forming emphasis and strikethrough
+fn match_emphasis(tokens: &mut [Token]) {
+    for closer in 0..tokens.len() {
+        while let Token::Delimiter {
+            ch,
+            len,
+            can_open: closer_can_open,
+            can_close: true,
+            used,
+        } = tokens[closer]
+            && len > used
+        {
+            let left = len - used;
+
+            let opener = (0..closer).rev().find(|&i| match tokens[i] {
+                Token::Delimiter {
+                    ch: c,
+                    len: l,
+                    can_open: true,
+                    can_close,
+                    used: u,
+                } if c == ch && l > u => {
+                    let opener_left = l - u;
+                    if ch == '~' {
+                        // strikethrough needs runs of the same length
+                        return opener_left == left && left <= 2;
+                    }
+                    // the "rule of 3" for runs that can both open and close
+                    !((can_close || closer_can_open)
+                        && (opener_left + left) % 3 == 0
+                        && (opener_left % 3 != 0 || left % 3 != 0))
+                }
+                _ => false,
+            });
+            let Some(opener) = opener else {
+                break;
+            };
+
+            let Token::Delimiter {
+                len: l, used: u, ..
+            } = tokens[opener]
+            else {
+                unreachable!()
+            };
+            let count = if left >= 2 && l - u >= 2 { 2 } else { 1 };
+            if let Token::Delimiter { used, .. } = &mut tokens[opener] {
+                *used += count;
+            }
+            if let Token::Delimiter { used, .. } = &mut tokens[closer] {
+                *used += count;
+            }
+            // delimiters in between can't be matched with the ones outside anymore
+            for token in &mut tokens[opener + 1..closer] {
+                if let Token::Delimiter {
+                    can_open,
+                    can_close,
+                    ..
+                } = token
+                {
+                    *can_open = false;
+                    *can_close = false;
+                }
+            }
+        }
+    }
+}
+
+/// parses a link starting at `[` and returns the link text and the length of the link.
+///
+/// Besides inline links `[text](destination "title")` this supports the reference
+/// links `[text][label]`, `[text][]` and `[text]` if the label is defined.
+fn link<'a>(text: &'a str, link_definitions: &HashSet<String>) -> Option<(&'a str, usize)> {
+    let end = 1 + text_end(&text[1..])?;
+    let link_text = &text[1..end];
+    let rest = &text[end + 1..];
+    let defined = |label: &str| link_definitions.contains(&normalize_label(label));
+
+    if let Some(len) = rest.strip_prefix('(').and_then(destination) {
+        return Some((link_text, end + 2 + len));
+    }
+    if let Some(label) = rest.strip_prefix('[')
+        && let Some(label_end) = label_end(label)
+    {
+        let label = &label[..label_end];
+        // a collapsed reference `[text][]` uses the text as label
+        let defined = if label.is_empty() {
+            defined(link_text)
+        } else {
+            defined(label)
+        };
+        return defined.then_some((link_text, end + label_end + 3));
+    }
+    defined(link_text).then_some((link_text, end + 1))
+}
+
+/// returns the offset of the `]` closing the link text. Brackets have to be
+/// balanced and the ones in code spans or escaped with `\` don't count.
+fn text_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut pos = 0;
+    while let Some(c) = text.get(pos..).and_then(|rest| rest.chars().next()) {
+        match c {
+            '\\' => pos += 1,
+            '`' => {
+                let len = text[pos..].chars().take_while(|&c| c == '`').count();
+                if let Some(end) = closing_backticks(&text[pos + len..], len) {
+                    pos += len + end;
+                }
+                pos += len;
+                continue;
+            }
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some(pos),
+            ']' => depth -= 1,
+            _ => {}
+        }
+        pos += text
+            .get(pos..)
+            .and_then(|rest| rest.chars().next())
+            .map_or(1, char::len_utf8);
+    }
+    None
+}
+
+/// returns the offset of the `]` closing a link label, which can't contain brackets
+pub(crate) fn label_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => return None,
+            ']' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// normalizes a link label, labels match case-insensitively and with any whitespace
+pub(crate) fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// parses the destination and optional title of an inline link after the `(` and
+/// returns the length up to and including the closing `)`
+fn destination(text: &str) -> Option<usize> {
+    let trimmed = text.trim_start();
+    let mut pos = text.len() - trimmed.len();
+
+    if let Some(bracketed) = trimmed.strip_prefix('<') {
+        let end = bracketed.find(['>', '<'])?;
+        if !bracketed[end..].starts_with('>') {
+            return None;
+        }
+        pos += end + 2;
+    } else {
+        // parentheses in the destination have to be balanced
+        let mut depth = 0;
+        let mut chars = trimmed.char_indices();
+        let mut end = trimmed.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    end = i;
+                    break;
+                }
+                ')' => depth -= 1,
+                c if c.is_whitespace() || c.is_control() => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        pos += end;
+    }
+
+    let rest = &text[pos..];
+    let trimmed = rest.trim_start();
+    pos += rest.len() - trimmed.len();
+
+    // optional title, it has to be separated from the destination by whitespace
+    let closing = match trimmed.chars().next() {
+        Some('"') => Some('"'),
+        Some('\'') => Some('\''),
+        Some('(') => Some(')'),
+        _ => None,
+    };
+    if let Some(closing) = closing
+        && pos > 0
+        && text[..pos].ends_with(char::is_whitespace)
+    {
+        let title = &trimmed[1..];
+        let mut chars = title.char_indices();
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if c == closing {
+                end = Some(i);
+                break;
+            }
+        }
+        pos += end? + 2;
+    }
+
+    let rest = &text[pos..];
+    let trimmed = rest.trim_start();
+    pos += rest.len() - trimmed.len();
+    trimmed.starts_with(')').then_some(pos + 1)
+}
+
+/// returns the length of an URI or email autolink like `<https://example.com>`
+fn autolink(text: &str) -> Option<usize> {
+    let end = text.find('>')?;
+    let inner = &text[1..end];
+    if inner.is_empty() || inner.contains(|c: char| c.is_whitespace() || c == '<') {
+        return None;
+    }
+
+    let uri = inner.split_once(':').is_some_and(|(scheme, _)| {
+        (2..=32).contains(&scheme.len())
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+    });
+    let email = inner.split_once('@').is_some_and(|(user, domain)| {
+        !user.is_empty() && domain.contains('.') && !domain.starts_with('.')
+    });
+
+    (uri || email).then_some(end + 1)
+}
+
+/// returns the length of inline HTML like a tag or a comment
+fn inline_html(text: &str) -> Option<usize> {
+    for (start, end) in [("<!--", "-->"), ("<?", "?>"), ("<![CDATA[", "]]>")] {
+        if let Some(rest) = text.strip_prefix(start) {
+            return rest.find(end).map(|i| start.len() + i + end.len());
+        }
+    }
+    if text[1..].starts_with('!') && text[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return text.find('>').map(|i| i + 1);
+    }
+
+    parser::complete_tag(text).map(|rest| text.len() - rest.len())
+}
+
+/// decodes an entity or numeric character reference and returns it with its length
+fn entity(text: &str) -> Option<(String, usize)> {
+    let end = text.find(';')?;
+    let name = &text[1..end];
+
+    let decoded = if let Some(hex) = name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+        (1..=6).contains(&hex.len()).then_some(())?;
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(dec) = name.strip_prefix('#') {
+        (1..=7).contains(&dec.len()).then_some(())?;
+        dec.parse().ok()
+    } else {
+        return Some((entities::decode(name)?.to_string(), end + 1));
+    }?;
+
+    // invalid code points are replaced like in the spec
+    let ch = char::from_u32(decoded)
+        .filter(|&c| c != '\0')
+        .unwrap_or('\u{fffd}');
+    Some((ch.to_string(), end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        struct TestCase<'a> {
+            input: &'a str,
+            display: &'a str,
+            plain: &'a str,
+        }
+        let test_cases = [
+            TestCase {
+                input: "Plain heading",
+                display: "Plain heading",
+                plain: "Plain heading",
+            },
+            TestCase {
+                input: "**New** ![badge](x.svg) <sup>beta</sup>",
+                display: "**New** beta",
+                plain: "New  beta",
+            },
+            TestCase {
+                input: "*emphasis*, __strong__ and ~~strike~~",
+                display: "*emphasis*, __strong__ and ~~strike~~",
+                plain: "emphasis, strong and strike",
+            },
+            TestCase {
+                input: "***both*** and *nested **strong***",
+                display: "***both*** and *nested **strong***",
+                plain: "both and nested strong",
+            },
+            TestCase {
+                input: "snake_case_name and 2 * 3 * 4",
+                display: "snake_case_name and 2 * 3 * 4",
+                plain: "snake_case_name and 2 * 3 * 4",
+            },
+            TestCase {
+                input: "The `config.toml` file",
+                display: "The `config.toml` file",
+                plain: "The config.toml file",
+            },
+            TestCase {
+                input: "Code `` `tick` `` and `*not emphasis*`",
+                display: "Code `` `tick` `` and `*not emphasis*`",
+                plain: "Code `tick` and *not emphasis*",
+            },
+            TestCase {
+                input: r"Escaped \*stars\* and \_underscores\_",
+                display: r"Escaped \*stars\* and \_underscores\_",
+                plain: "Escaped *stars* and _underscores_",
+            },
+            TestCase {
+                input: "Q&amp;A &copy; &#169; &#xA9;",
+                display: "Q&amp;A &copy; &#169; &#xA9;",
+                plain: "Q&A © © ©",
+            },
+            TestCase {
+                input: "Espa&ntilde;ol, &Eacute;t&eacute; and I &hearts; Rust",
+                display: "Espa&ntilde;ol, &Eacute;t&eacute; and I &hearts; Rust",
+                plain: "Español, Été and I ♥ Rust",
+            },
+            TestCase {
+                input: "Unknown &entity; and AT&T",
+                display: "Unknown &entity; and AT&T",
+                plain: "Unknown &entity; and AT&T",
+            },
+            TestCase {
+                input: "Visit <https://example.com> or <mail@example.com>",
+                display: "Visit https://example.com or mail@example.com",
+                plain: "Visit https://example.com or mail@example.com",
+            },
+            TestCase {
+                input: "Footnote[^1] and <!-- comment -->HTML<br/>",
+                display: "Footnote and HTML",
+                plain: "Footnote and HTML",
+            },
+            TestCase {
+                input: "A [**bold** link](https://example.com)",
+                display: "A **bold** link",
+                plain: "A bold link",
+            },
+            TestCase {
+                input: "Logo ![alt](img.png) and ![badge][ref]",
+                display: "Logo and",
+                plain: "Logo  and ",
+            },
+            TestCase {
+                input: "[Code `]`](https://example.com) in links",
+                display: "Code `]` in links",
+                plain: "Code ] in links",
+            },
+            TestCase {
+                input: "1 < 2 and a lonely ` backtick",
+                display: "1 < 2 and a lonely ` backtick",
+                plain: "1 < 2 and a lonely ` backtick",
+            },
+        ];
+
+        let link_definitions = HashSet::from(["ref".to_string()]);
+
+        for test_case in test_cases {
+            let inline = render(test_case.input, &link_definitions);
+            assert_eq!(inline.display, test_case.display, "{}", test_case.input);
+            assert_eq!(inline.plain, test_case.plain, "{}", test_case.input);
+        }
+    }
+
+    #[test]
+    fn test_render_links() {
+        struct TestCase {
+            input: &'static str,
+            expected: String,
+        }
+        let test_cases = [
+            TestCase {
+                input: "This is a [link](https://example.com) in a Markdown text.",
+                expected: String::from("This is a link in a Markdown text."),
+            },
+            TestCase {
+                input: "This is a [link](https://example.com) and [another one](https://example.org) in a Markdown text.",
+                expected: String::from("This is a link and another one in a Markdown text."),
+            },
+            TestCase {
+                input: "This is a text without any links.",
+                expected: String::from("This is a text without any links."),
+            },
+            TestCase {
+                input: "",
+                expected: String::from(""),
+            },
+            TestCase {
+                input: "This is a [link](https://example.com) with some text after it.",
+                expected: String::from("This is a link with some text after it."),
+            },
+            TestCase {
+                input: "No link [here]",
+                expected: String::from("No link [here]"),
+            },
+            TestCase {
+                input: "Nested [foo [bar]](https://example.com) brackets",
+                expected: String::from("Nested foo [bar] brackets"),
+            },
+            TestCase {
+                input: "[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language)) on Wikipedia",
+                expected: String::from("Rust on Wikipedia"),
+            },
+            TestCase {
+                input: "With [title](<docs/my file.md> \"Title (1)\") and [more](x 'y')",
+                expected: String::from("With title and more"),
+            },
+            TestCase {
+                input: "Reference [links][ref], [Foo Bar][] and [REF] shortcuts",
+                expected: String::from("Reference links, Foo Bar and REF shortcuts"),
+            },
+            TestCase {
+                input: "Undefined [links][nope] and [nope]",
+                expected: String::from("Undefined [links][nope] and [nope]"),
+            },
+            TestCase {
+                input: "Not a [link] (https://example.com)",
+                expected: String::from("Not a [link] (https://example.com)"),
+            },
+        ];
+        let link_definitions = HashSet::from(["ref".to_string(), "foo bar".to_string()]);
+
+        for test_case in &test_cases {
+            let inline = render(test_case.input, &link_definitions);
+            assert_eq!(inline.display, test_case.expected);
+            assert_eq!(inline.plain, test_case.expected);
+        }
+    }
+}
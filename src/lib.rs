@@ -1,10 +1,27 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
+use std::ops::Range;
 use std::path::Path;
 
+mod diagnostic;
+mod entities;
+mod error;
+mod html;
+mod inline;
+mod list;
+mod outline;
+mod parser;
+mod slug;
+mod template;
+
+pub use diagnostic::{Diagnostic, Severity};
+pub use error::Error;
+pub use list::{Bullet, Indent, Numbering};
+pub use outline::{Heading, Outline, TocNode, TocTree, parse_headings};
+pub use slug::SlugStyle;
+pub use template::Template;
+
 const COMMENT_BEGIN: &str = "<!-- BEGIN mktoc -->";
 const COMMENT_END: &str = "<!-- END mktoc -->";
 
@@ -19,30 +36,130 @@ pub struct Config {
     pub wrap_in_details: bool,
     #[serde(default)]
     pub start_comment: String,
+    #[serde(default)]
+    pub slug_style: SlugStyle,
+    /// Remove inline code and emphasis from the ToC entries as well
+    #[serde(default)]
+    pub strip_formatting: bool,
+    /// Headings left out of the ToC, either the heading text or a regex like `/^v\d/`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Indent entries by their absolute heading level like mktoc up to version 5,
+    /// levels 1 and 2 at the root, level 3 with two spaces and so on
+    #[serde(default)]
+    pub legacy_indent: bool,
+    /// Marker of the entries, `-`, `*` or `+`
+    #[serde(default)]
+    pub bullet: Bullet,
+    /// Render the ToC as ordered list (`1.`, `2.`) instead
+    #[serde(default)]
+    pub ordered: bool,
+    /// Indentation of nested entries, two spaces (three for ordered lists) if not set
+    #[serde(default)]
+    pub indent: Option<Indent>,
+    /// Prefix the entries with hierarchical numbers like `1.2.`
+    #[serde(default)]
+    pub numbering: Option<Numbering>,
+    /// Leave top level entries unnumbered and start at 1 again below each of them
+    #[serde(default)]
+    pub restart_numbering: bool,
+    /// Write the numbers into the headings of the document instead of the ToC entries,
+    /// see [`renumber_headings`]
+    #[serde(default)]
+    pub renumber_headings: bool,
+    /// Render the ToC as nested HTML lists instead of a Markdown list
+    #[serde(default)]
+    pub html: bool,
+    /// Header, entry line and footer the ToC is rendered with instead of the Markdown list
+    #[serde(default)]
+    pub template: Option<Template>,
+    /// Template file, relative to the Markdown file, that is read by [`make_toc`] and
+    /// takes precedence over `template`
+    #[serde(default)]
+    pub template_file: Option<String>,
+    /// Fail on unknown keys in the inline config instead of warning about them. Also
+    /// applies to the inline config if it is set in the provided config.
+    #[serde(default)]
+    pub strict: bool,
 }
 
 impl Config {
-    // ensure_min_max sets the max_depth and min_depth values to
-    // their defaults in case they were configured wrong.
-    fn ensure_min_max(&mut self) {
-        if (self.max_depth > 6) || (self.max_depth < 1) {
-            self.max_depth = default_max_depth();
-            eprintln!(
-                "WARNING: max_depth out of bound. Default value '{}' used.",
-                self.max_depth
-            );
+    // check_depths returns an error if max_depth or min_depth are out of bound
+    // or min_depth is greater than max_depth, which would result in an empty ToC
+    fn check_depths(&self) -> Result<(), Error> {
+        for (name, value) in [("min_depth", self.min_depth), ("max_depth", self.max_depth)] {
+            if !(1..=6).contains(&value) {
+                return Err(Error::DepthOutOfRange { name, value });
+            }
+        }
+        if self.min_depth > self.max_depth {
+            return Err(Error::InvertedDepths {
+                min_depth: self.min_depth,
+                max_depth: self.max_depth,
+            });
         }
 
-        if (self.min_depth < 1) || (self.min_depth > 6) {
-            self.min_depth = default_min_depth();
-            eprintln!(
-                "WARNING: min_depth out of bound. Default value '{}' used.",
-                self.min_depth
-            );
+        Ok(())
+    }
+
+    // exclude_patterns turns the exclude list into regexes. Entries wrapped
+    // in slashes are regexes, everything else has to match the whole text.
+    // Invalid regexes are skipped, see `diagnostics`.
+    fn exclude_patterns(&self) -> Vec<Regex> {
+        self.exclude
+            .iter()
+            .filter_map(|entry| exclude_pattern(entry).ok())
+            .collect()
+    }
+
+    // diagnostics returns warnings about invalid exclude patterns and options that are
+    // ignored, at the given line of the inline config
+    fn diagnostics(&self, line: Option<usize>) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self
+            .exclude
+            .iter()
+            .filter_map(|entry| {
+                let e = exclude_pattern(entry).err()?;
+                Some(Diagnostic::warning(
+                    format!("invalid exclude pattern '{}' ignored: {}", entry, e),
+                    line,
+                ))
+            })
+            .collect();
+
+        if self.template_file.is_some() && self.template.is_some() {
+            diagnostics.push(Diagnostic::warning(
+                String::from("template ignored, template_file takes precedence"),
+                line,
+            ));
+        }
+        if self.html && (self.template.is_some() || self.template_file.is_some()) {
+            diagnostics.push(Diagnostic::warning(
+                String::from("html ignored, the template takes precedence"),
+                line,
+            ));
         }
+        if self.restart_numbering && self.numbering.is_none() && !self.renumber_headings {
+            diagnostics.push(Diagnostic::warning(
+                String::from("restart_numbering ignored without numbering or renumber_headings"),
+                line,
+            ));
+        }
+
+        diagnostics
     }
 }
 
+/// turns an entry of the exclude list into a regex
+fn exclude_pattern(entry: &str) -> Result<Regex, regex::Error> {
+    let pattern = match entry.strip_prefix('/').and_then(|e| e.strip_suffix('/')) {
+        Some(pattern) => pattern.to_string(),
+        None => format!("^{}$", regex::escape(entry.trim())),
+    };
+
+    Regex::new(&pattern)
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -50,6 +167,20 @@ impl Default for Config {
             max_depth: default_max_depth(),
             wrap_in_details: false,
             start_comment: COMMENT_BEGIN.to_string(),
+            slug_style: SlugStyle::default(),
+            strip_formatting: false,
+            exclude: Vec::new(),
+            legacy_indent: false,
+            bullet: Bullet::default(),
+            ordered: false,
+            indent: None,
+            numbering: None,
+            restart_numbering: false,
+            renumber_headings: false,
+            html: false,
+            template: None,
+            template_file: None,
+            strict: false,
         }
     }
 }
@@ -60,6 +191,20 @@ impl PartialEq for Config {
             && self.min_depth == other.min_depth
             && self.wrap_in_details == other.wrap_in_details
             && self.start_comment == other.start_comment
+            && self.slug_style == other.slug_style
+            && self.strip_formatting == other.strip_formatting
+            && self.exclude == other.exclude
+            && self.legacy_indent == other.legacy_indent
+            && self.bullet == other.bullet
+            && self.ordered == other.ordered
+            && self.indent == other.indent
+            && self.numbering == other.numbering
+            && self.restart_numbering == other.restart_numbering
+            && self.renumber_headings == other.renumber_headings
+            && self.html == other.html
+            && self.template == other.template
+            && self.template_file == other.template_file
+            && self.strict == other.strict
     }
 }
 
@@ -72,221 +217,706 @@ fn default_max_depth() -> i32 {
 }
 
 /// reads a file into a mutable string
-fn read_file<P>(file_path: P) -> Result<String, ::std::io::Error>
+fn read_file<P>(file_path: P) -> Result<String, Error>
 where
     P: AsRef<Path>,
 {
-    let mut file = File::open(file_path)?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
-
-    Ok(contents)
+    let bytes = std::fs::read(file_path)?;
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        Error::InvalidUtf8 {
+            line: valid.iter().filter(|&&byte| byte == b'\n').count() + 1,
+        }
+    })
 }
 
-/// remove links from Markdown
-fn strip_markdown_links(text: &str) -> String {
-    let pattern = r"(?P<before>[^\[]*)(\[(?P<text>[^\]]+)\]\((?P<link>[^)]+)\))(?P<after>[^\[]*)";
-    let re_link = regex::Regex::new(pattern).unwrap();
-    let txt = re_link.replace_all(text, "$before$text$after");
-
-    txt.to_string()
+/// returns the line of the byte offset in the content, counted from 1
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
 }
 
-/// convert the text to an URL. The replacements are a best-effort guess based on observations of GitHub link rendering
+/// convert the plain text of a heading to an URL the same way GitHub does (see
+/// github-slugger). The text is lowercased, everything but letters, marks, digits, `_`, `-` and spaces is removed and
+/// spaces are replaced with `-`.
 fn text_to_url(text: &str) -> String {
-    let txt = strip_markdown_links(text);
-    txt.trim()
+    // `\w` in Unicode mode matches letters, marks, decimal digits and connector punctuation
+    let re_punctuation = Regex::new(r"[^\w\- ]").unwrap();
+    re_punctuation
+        .replace_all(&text.trim().to_lowercase(), "")
         .replace(' ', "-")
-        .replace(
-            [
-                '(', ')', '`', '´', '\'', '"', '[', ']', '{', '}', '?', '¿', '!', '¡', '.', ',',
-                '\\', '/', ':', ';', '§', '$', '%', '&', '=', '^', '°', '#', '+', '*', '<', '>',
-            ],
-            "",
-        )
-        .to_ascii_lowercase()
 }
 
 /// parses a string and extracts all headlines to build a table of contents
 ///
-/// Uses a basic regex `((#{1,6}\s))((.*))` to parse headings out of the provided content string
+/// Headings are found by a block scanner following the CommonMark spec, so lines
+/// inside code blocks or HTML blocks are skipped and ATX headings may be indented
+/// by up to 3 spaces or end in a closing sequence like `## Heading ##`.
+/// HTML headings (`<h2 id="install">Install</h2>`) are included as well, their
+/// `id` attribute is used as anchor if present. The same goes for explicit ids
+/// in an attribute block like `## Installation {#install}`.
+///
+/// A heading can get a shorter ToC entry with a `<!-- mktoc: label="Setup" -->` line
+/// before it or a `data-toc-label` attribute like `## Setting up {data-toc-label="Setup"}`.
+///
+/// Headings after a `<!-- mktoc-ignore -->` line, between `<!-- mktoc-ignore-start -->`
+/// and `<!-- mktoc-ignore-end -->` or matching an entry of `exclude` are left out.
+///
+/// Anchors are created from the text as it shows up in the rendered document, without
+/// any inline Markdown or HTML. The ToC entries keep inline code and emphasis unless
+/// `strip_formatting` is set.
+///
+/// The headings are collected by [`parse_headings`] and nested by [`TocTree`], which
+/// can be used directly to work with the outline of a document.
+///
+/// The entries are rendered with the [`Template`] of the config, by default a Markdown
+/// list. Its entry line can use the variables `{indent}`, `{marker}`, `{text}` (as
+/// Markdown), `{text_html}` (escaped plain text), `{anchor}`, `{level}` (heading level), `{depth}` (nesting depth, 0 at the root),
+/// `{number}` (hierarchical number like `1.2.`) and `{index}` (position in its list).
 ///
 /// ```
 /// use mktoc::*;
 /// let new_toc = generate_toc(String::from("# Hello\n## World"), Config::default());
-/// assert_eq!(new_toc, "<!-- BEGIN mktoc -->\n\n- [Hello](#hello)\n- [World](#world)\n<!-- END mktoc -->");
+/// assert_eq!(new_toc, "<!-- BEGIN mktoc -->\n\n- [Hello](#hello)\n  - [World](#world)\n<!-- END mktoc -->");
 /// ```
 pub fn generate_toc(original_content: String, config: Config) -> String {
-    let mut already_found_code_open = false;
-    let mut code_block_found = false;
-    let mut found_headings: HashMap<String, i64> = HashMap::new();
-    let mut new_toc = String::from("");
-    let re = regex::Regex::new(r"((#{1,6}\s))((.*))").unwrap();
+    let tree = TocTree::new(parse_headings(&original_content, &config), &config);
+    let entries: Vec<&TocNode> = tree.iter().collect();
+    let mut new_toc = match &config.template {
+        None if config.html => html::render(&entries, &config),
+        template => render_template(&entries, &config, &template.clone().unwrap_or_default()),
+    };
 
-    for line in original_content.lines() {
-        if line.starts_with("```") {
-            code_block_found = true;
+    if config.wrap_in_details {
+        // HTML lists may be indented, only Markdown lists could turn into code blocks
+        if config.template.is_some() || !config.html {
+            new_toc = cleanup_wrapped_toc(new_toc);
         }
+        new_toc = format!(
+            "{}\n<details><summary>Table of Contents</summary>\n{}\n\n</details>\n{}\n",
+            config.start_comment, new_toc, COMMENT_END
+        );
+    } else if config.template.is_some() || config.html {
+        // HTML blocks only end at a blank line, without it the end comment and the
+        // headings after it would be part of the HTML of the ToC
+        new_toc = format!("{}\n{}\n\n{}", config.start_comment, new_toc, COMMENT_END);
+    } else {
+        new_toc = format!("{}\n{}\n{}", config.start_comment, new_toc, COMMENT_END);
+    }
 
-        if !code_block_found && !already_found_code_open && line.starts_with('#') {
-            // Check if the regex matches, if it doesn't skip the loop.
-            let caps = match re.captures(line) {
-                Some(matched) => matched,
-                None => {
-                    continue;
-                }
-            };
+    new_toc
+}
 
-            let level: i32 = (caps.get(2).unwrap().as_str().chars().count() - 1) as i32;
+/// renders the entries with the header, entry line and footer of the template
+fn render_template(entries: &[&TocNode], config: &Config, template: &Template) -> String {
+    let mut new_toc = String::from("");
+    // `{number}` is available without numbering enabled as well
+    let numbering = config.numbering.unwrap_or(Numbering::DecimalDot);
 
-            if level < config.min_depth {
-                continue;
-            }
+    if !template.header.is_empty() {
+        new_toc = format!("\n{}", template::fill(&template.header, |_| None));
+    }
 
-            if level > config.max_depth {
-                continue;
+    for entry in entries {
+        let index = entry.numbers[entry.depth];
+        let marker = if config.ordered {
+            format!("{}.", index)
+        } else {
+            config.bullet.to_string()
+        };
+        let text = entry_text(entry, config);
+
+        let line = template::fill(&template.entry, |variable| match variable {
+            "indent" => Some(match config.indent {
+                Some(indent) => indent.repeat(entry.depth),
+                None => list::default_indent(&entry.numbers, entry.depth, config.ordered),
+            }),
+            "marker" => Some(marker.clone()),
+            "text" => Some(text.clone()),
+            "text_html" => Some(html::text(entry, config)),
+            "anchor" => Some(entry.heading.anchor.clone()),
+            "level" => Some(entry.heading.level.to_string()),
+            "depth" => Some(entry.depth.to_string()),
+            "number" => {
+                Some(format_number(entry, numbering, config.restart_numbering).unwrap_or_default())
             }
+            "index" => Some(index.to_string()),
+            _ => None,
+        });
+        new_toc = format!("{}\n{}", new_toc, line);
+    }
 
-            let text = strip_markdown_links(caps.get(3).unwrap().as_str());
-            let mut link = text_to_url(text.as_str());
-
-            // check if a heading has already been found
-            match found_headings.get_key_value(&link) {
-                Some(entry) => {
-                    // if it has been found already, we count how many times
-                    found_headings.insert(link.clone(), entry.1 + 1);
-                    let new_content = found_headings.get_key_value(&link).unwrap();
-                    // the number is appended to the link
-                    link = format!("{}-{}", link, new_content.1);
-                }
-                None => {
-                    // if not found, initialize with 0
-                    found_headings.entry(link.clone()).or_insert(0);
-                }
-            }
+    if !template.footer.is_empty() {
+        new_toc = format!(
+            "{}\n{}",
+            new_toc,
+            template::fill(&template.footer, |_| None)
+        );
+    }
 
-            let spaces = match level {
-                3 => String::from("  "),
-                4 => String::from("    "),
-                5 => String::from("      "),
-                6 => String::from("        "),
-                _ => String::from(""),
-            };
+    new_toc
+}
 
-            new_toc = format!(
-                "{old}\n{spaces}- [{text}](#{link})",
-                old = new_toc.as_str(),
-                spaces = spaces,
-                text = text,
-                link = link
-            );
+/// returns the text of the entry, prefixed with its number if numbering is enabled
+fn entry_text(entry: &TocNode, config: &Config) -> String {
+    // renumbered headings already start with their number
+    match entry_number(entry, config) {
+        Some(number) if !config.renumber_headings => {
+            format!("{} {}", number, entry.heading.text)
         }
+        _ => entry.heading.text.clone(),
+    }
+}
 
-        if code_block_found && already_found_code_open {
-            code_block_found = false;
-            already_found_code_open = false;
-        }
+/// returns the hierarchical number of the entry if numbering is enabled
+fn entry_number(entry: &TocNode, config: &Config) -> Option<String> {
+    let numbering = match config.numbering {
+        Some(numbering) => numbering,
+        // renumbering headings without a format uses the default one
+        None if config.renumber_headings => Numbering::DecimalDot,
+        None => return None,
+    };
 
-        if line.starts_with("```") {
-            already_found_code_open = true;
-        }
+    format_number(entry, numbering, config.restart_numbering)
+}
+
+/// formats the hierarchical number of the entry
+fn format_number(entry: &TocNode, numbering: Numbering, restart_numbering: bool) -> Option<String> {
+    // top level entries are not numbered when restarting below each of them
+    if restart_numbering && entry.depth == 0 {
+        return None;
     }
+    let start = usize::from(restart_numbering);
+    // parents missing with legacy_indent have no number of their own and are left out
+    let numbers: Vec<usize> = entry.numbers[start..]
+        .iter()
+        .copied()
+        .filter(|&number| number > 0)
+        .collect();
+
+    Some(numbering.format(&numbers))
+}
 
-    if config.wrap_in_details {
-        let new_toc_content = cleanup_wrapped_toc(new_toc);
-        new_toc = format!(
-            "{}\n<details><summary>Table of Contents</summary>\n{}\n\n</details>\n{}\n",
-            config.start_comment, new_toc_content, COMMENT_END
-        );
-    } else {
-        new_toc = format!("{}\n{}\n{}", config.start_comment, new_toc, COMMENT_END);
+/// returns a regex matching a number in the given format at the start of a heading,
+/// followed by whitespace. Any number of parts is matched, so the numbers of headings
+/// that moved to another depth are replaced as well.
+fn number_regex(numbering: Numbering) -> Regex {
+    Regex::new(&format!(r"^{}[ \t]+", numbering.pattern())).unwrap()
+}
+
+/// returns how deep an entry of the given level is nested in the ToC and updates
+/// `parent_levels`, the levels of the entries above it that are still open.
+///
+/// The first entry is at the root and every entry is nested one step below the
+/// previous entry with a lower level. Skipped levels don't add extra indentation,
+/// so `##` followed by `####` results in a valid nested list.
+fn nesting_depth(parent_levels: &mut Vec<i32>, level: i32) -> usize {
+    while parent_levels.last().is_some_and(|&parent| parent >= level) {
+        parent_levels.pop();
     }
+    let depth = parent_levels.len();
+    parent_levels.push(level);
 
-    new_toc
+    depth
+}
+
+/// returns an anchor for the slug that is not in `found_headings` yet and adds it.
+///
+/// `found_headings` counts how often each slug has been seen. A repeated slug gets
+/// the next number appended, which is checked against all anchors again. This way
+/// `Foo`, `Foo` and `Foo 1` result in `foo`, `foo-1` and `foo-1-1` like on GitHub.
+fn unique_anchor(
+    found_headings: &mut HashMap<String, i64>,
+    slug: String,
+    style: SlugStyle,
+) -> (String, usize) {
+    let mut link = slug.clone();
+    let mut index = 0;
+    while found_headings.contains_key(&link) {
+        let count = found_headings.get_mut(&slug).unwrap();
+        *count += 1;
+        index = *count as usize;
+        link = style.duplicate(&slug, *count);
+    }
+    found_headings.insert(link.clone(), 0);
+
+    (link, index)
 }
 
 /// Removes extra spaces to prevent rendering of code blocks when wrapped
 /// in HTML element.
 fn cleanup_wrapped_toc(input: String) -> String {
-    // 4 spaces will render a code block if wrapped inside a HTML element.
-    // So we strip away all lines staring with 4 spaces.
-    let re = Regex::new(r"(?m)^ {4}").unwrap();
-
-    re.replace_all(&input, "").to_string()
-}
-
-/// Parses the JSON from the comment and returns the config or a default config if
-/// no config was found. The second return parameter indicated if a JSON config was found (true), or if
-/// the default was returned (false)
-fn parse_json_config(text: &str) -> (Config, bool) {
-    let mut json_config_found = false;
-    let mut start_comment = COMMENT_BEGIN.to_string();
-    let re = Regex::new(r"<!--\s*BEGIN mktoc\s*(?P<json>\{.*\})\s*-->").unwrap();
-    let json_str = match re.captures(text) {
-        Some(captures) => captures.name("json").unwrap().as_str(),
-        None => "",
-    };
+    // 4 spaces or a tab will render a code block if wrapped inside a HTML element.
+    // Nested entries are fine, so only the indentation all entries have in
+    // common is stripped away, e.g. when the first entry is a level 4 heading.
+    let indent = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+
+    input
+        .lines()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    // if a json config is found it will be injected into the start comment.
-    if !json_str.is_empty() {
-        start_comment = format!("<!-- BEGIN mktoc {} -->", json_str);
-        json_config_found = true;
-    }
+/// returns the JSON of the inline config in the begin comment and its byte offset
+fn inline_config_json(text: &str) -> Option<(&str, usize)> {
+    let begin = find_markers(text)?.begin;
+    let re = Regex::new(r"(?s)<!--\s*BEGIN mktoc\s*(?P<json>.*?)\s*-->").unwrap();
+    // anything after `BEGIN mktoc` is taken as config, so a broken one isn't dropped
+    let json = re
+        .captures(&text[begin.clone()])?
+        .name("json")
+        .filter(|json| !json.is_empty())?;
+
+    Some((json.as_str(), begin.start + json.start()))
+}
+
+/// Parses the JSON from the comment and returns the config, or `None` if the comment
+/// has no config. Invalid JSON results in an error with its position in the text.
+fn parse_json_config(text: &str) -> Result<Option<Config>, Error> {
+    let Some((json, offset)) = inline_config_json(text) else {
+        return Ok(None);
+    };
 
     // parse the JSON string as a Config struct
-    let mut config: Config = match serde_json::from_str(json_str) {
-        Ok(config) => config,
-        Err(_e) => {
-            json_config_found = false;
-            Config::default()
+    let mut config: Config = serde_json::from_str(json).map_err(|e| {
+        // serde_json counts lines and columns from 1 within the JSON
+        let json_line_start: usize = json
+            .split_inclusive('\n')
+            .take(e.line().saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let error_offset =
+            (offset + json_line_start + e.column().saturating_sub(1)).min(text.len());
+        // the position within the JSON is replaced by the one in the text
+        let message = e.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(end) => message[..end].to_string(),
+            None => message,
+        };
+        let message = match invalid_key(json) {
+            Some(key) => format!("invalid value for `{}`: {}", key, message),
+            None => message,
+        };
+
+        invalid_config(text, message, error_offset)
+    })?;
+    // the json config is injected into the start comment
+    config.start_comment = format!("<!-- BEGIN mktoc {} -->", json);
+
+    Ok(Some(config))
+}
+
+/// returns the first key of the JSON object whose value has the wrong type, or
+/// `None` if the JSON itself is invalid
+fn invalid_key(json: &str) -> Option<String> {
+    let serde_json::Value::Object(object) = serde_json::from_str(json).ok()? else {
+        return None;
+    };
+
+    object.into_iter().find_map(|(key, value)| {
+        let single = serde_json::Value::Object([(key.clone(), value)].into_iter().collect());
+        serde_json::from_value::<Config>(single)
+            .is_err()
+            .then_some(key)
+    })
+}
+
+/// returns the keys of the inline config that are not config options, with their
+/// byte offset in the text
+fn unknown_keys(text: &str) -> Vec<(String, usize)> {
+    let Some((json, offset)) = inline_config_json(text) else {
+        return Vec::new();
+    };
+    let Ok(serde_json::Value::Object(object)) = serde_json::from_str(json) else {
+        return Vec::new();
+    };
+    let Ok(serde_json::Value::Object(known)) = serde_json::to_value(Config::default()) else {
+        return Vec::new();
+    };
+
+    object_keys(json)
+        .into_iter()
+        .filter(|(key, _)| object.contains_key(key) && !known.contains_key(key))
+        .map(|(key, key_offset)| (key, offset + key_offset))
+        .collect()
+}
+
+/// returns the keys of the top level object of valid JSON with their byte offset,
+/// strings in values and nested objects are skipped
+fn object_keys(json: &str) -> Vec<(String, usize)> {
+    let bytes = json.as_bytes();
+    let mut keys = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            b'"' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    // skip the escaped character
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                let end = (i + 1).min(bytes.len());
+                // a string in the top level object followed by a colon is a key
+                if depth == 1
+                    && json[end..].trim_start().starts_with(':')
+                    && let Ok(key) = serde_json::from_str::<String>(&json[start..end])
+                {
+                    keys.push((key, start));
+                }
+            }
+            _ => {}
         }
+        i += 1;
+    }
+
+    keys
+}
+
+/// returns the message for an unknown key, with the closest config option if the
+/// key looks like a typo of it
+fn unknown_key_message(key: &str) -> String {
+    let known = match serde_json::to_value(Config::default()) {
+        Ok(serde_json::Value::Object(known)) => known,
+        _ => serde_json::Map::new(),
     };
+    let closest = known
+        .keys()
+        .map(|option| (edit_distance(key, option), option))
+        .min()
+        .filter(|(distance, _)| *distance <= 2);
+
+    match closest {
+        Some((_, option)) => format!("unknown key `{}`, did you mean `{}`?", key, option),
+        None => format!("unknown key `{}`", key),
+    }
+}
 
-    // ensures the min_depth and max_depth are within scope
-    config.ensure_min_max();
-    // assign start comment
-    config.start_comment = start_comment;
+/// returns the number of characters that have to be inserted, removed, replaced or
+/// swapped with their neighbour to turn one string into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            rows[i][j] = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                rows[i][j] = rows[i][j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+    }
 
-    (config, json_config_found)
+    rows[a.len()][b.len()]
 }
 
-/// Attempts to parse the JSON from the Markdown comment, otherwise uses the provided fallback
-fn parse_json_config_or_use_provided(content: &str, cnf: Config) -> Config {
-    let (json_config, json_config_found) = parse_json_config(content);
+/// returns an invalid config error at the byte offset of the text, the column is
+/// counted in characters
+fn invalid_config(text: &str, message: String, offset: usize) -> Error {
+    // the offset of serde_json may point into a multi-byte character
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+    Error::InvalidConfig {
+        message,
+        line: line_number(text, offset),
+        column: text[line_start..offset].chars().count() + 1,
+    }
+}
 
+/// Attempts to parse the JSON from the Markdown comment, otherwise uses the provided
+/// fallback. Fails if the JSON is invalid, the depths are out of bound or, with
+/// `strict` set in either config, the inline config has unknown keys.
+fn parse_json_config_or_use_provided(content: &str, cnf: Config) -> Result<Config, Error> {
+    let strict = cnf.strict;
     // if a json config was found it takes priority over any other config
-    if json_config_found {
-        return json_config;
+    let mut config = parse_json_config(content)?.unwrap_or(cnf);
+    config.strict |= strict;
+    if config.strict
+        && let Some((key, offset)) = unknown_keys(content).into_iter().next()
+    {
+        return Err(invalid_config(content, unknown_key_message(&key), offset));
     }
+    // ensures the min_depth and max_depth are within scope
+    config.check_depths()?;
+
+    Ok(config)
+}
+
+/// Byte ranges of the ToC markers in a document
+struct Markers {
+    /// the begin comment including the inline config
+    begin: Range<usize>,
+    /// the first end comment after the begin comment
+    end: Option<Range<usize>>,
+}
 
-    cnf
+/// finds the ToC markers in the content. Only comments that start a line of an
+/// HTML block count, so markers shown in code blocks or front matter are ignored.
+fn find_markers(content: &str) -> Option<Markers> {
+    let html_lines = parser::scan(content).html_lines;
+
+    let begin_line = html_lines
+        .iter()
+        .find(|line| content[line.start..].starts_with("<!-- BEGIN mktoc"))?;
+    // the begin comment may span multiple lines
+    let begin_end = begin_line.start + content[begin_line.start..].find("-->")? + 3;
+
+    let end = html_lines
+        .iter()
+        .filter(|line| line.start >= begin_end)
+        .find(|line| content[line.start..].starts_with(COMMENT_END))
+        .map(|line| line.start..line.start + COMMENT_END.len());
+
+    Some(Markers {
+        begin: begin_line.start..begin_end,
+        end,
+    })
+}
+
+/// returns an error unless the content has a begin marker followed by an end marker
+fn check_markers(content: &str) -> Result<(), Error> {
+    match find_markers(content) {
+        Some(Markers { end: Some(_), .. }) => Ok(()),
+        Some(Markers { begin, end: None }) => Err(Error::UnbalancedMarkers {
+            line: line_number(content, begin.start),
+        }),
+        None => {
+            let html_lines = parser::scan(content).html_lines;
+            match html_lines
+                .iter()
+                .find(|line| content[line.start..].starts_with(COMMENT_END))
+            {
+                Some(end) => Err(Error::UnbalancedMarkers {
+                    line: line_number(content, end.start),
+                }),
+                None => Err(Error::MissingMarkers),
+            }
+        }
+    }
 }
 
 /// add the generated ToC into the original file
 pub fn add_toc(original_content: String, new_toc: String) -> String {
     // get the ToC position and replace it with the new ToC
-    let re_toc = Regex::new(r"(?ms)^(<!-- BEGIN mktoc(.*?)-->)(.*?)(<!-- END mktoc -->)").unwrap();
-    let res: String = re_toc
-        .replace(original_content.as_str(), new_toc.as_str())
-        .into_owned();
+    match find_markers(&original_content) {
+        Some(Markers {
+            begin,
+            end: Some(end),
+        }) => format!(
+            "{}{}{}",
+            &original_content[..begin.start],
+            new_toc,
+            &original_content[end.end..]
+        ),
+        _ => original_content,
+    }
+}
+
+/// writes the hierarchical numbers of the ToC entries into the headings of the document
+///
+/// Numbers at the start of the headings are replaced, so running it again results in
+/// the same document. The format is taken from `numbering` and defaults to `1.2.`.
+/// A number is replaced whatever its value if it has the shape of the format, e.g.
+/// `2.3.` with the default format. Numbers in other formats are kept as part of the
+/// heading text, so are years like `2024` and acronyms like `API` with the default.
+/// Only ATX headings (`## Heading`) in the ToC are renumbered, the anchors change with
+/// the text and are created from the new text by [`generate_toc`].
+///
+/// ```
+/// use mktoc::*;
+/// let content = renumber_headings(String::from("# Hello\n## 3. World"), Config::default());
+/// assert_eq!(content, "# 1. Hello\n## 1.1. World");
+/// ```
+pub fn renumber_headings(original_content: String, config: Config) -> String {
+    let re_number = number_regex(config.numbering.unwrap_or(Numbering::DecimalDot));
+    let config = Config {
+        renumber_headings: true,
+        ..config
+    };
+    let mut content = original_content.clone();
+
+    // replacing from the end keeps the offsets of the previous headings valid
+    let tree = TocTree::new(parse_headings(&original_content, &config), &config);
+    let entries: Vec<&TocNode> = tree.iter().collect();
+    for entry in entries.into_iter().rev() {
+        let Some(start) = entry.heading.offset else {
+            continue;
+        };
+        let old_number = re_number
+            .find(&original_content[start..])
+            .map_or(0, |number| number.end());
+        let new_number = match entry_number(entry, &config) {
+            Some(number) => format!("{} ", number),
+            None => String::new(),
+        };
+        content.replace_range(start..start + old_number, &new_number);
+    }
 
-    res
+    content
 }
 
 /// takes a file path as `String` and returns a table of contents for the file
-pub fn make_toc<P>(file_path_in: P, cnf: Config) -> Result<String, ::std::io::Error>
+///
+/// Fails if the file has no markers, the inline config is invalid or the depths are
+/// out of bound, see [`Error`]. Warnings are dropped, use [`make_toc_with_diagnostics`]
+/// to get them as well.
+pub fn make_toc<P>(file_path_in: P, cnf: Config) -> Result<String, Error>
+where
+    P: AsRef<Path>,
+{
+    make_toc_with_diagnostics(file_path_in, cnf).map(|(toc, _)| toc)
+}
+
+/// same as [`make_toc`], but returns the warnings about the file and its config as well
+///
+/// The library doesn't print anything. Warnings are reported for invalid exclude
+/// patterns, ignored config options and additional markers, skipped heading levels
+/// are reported as [`Severity::Info`].
+pub fn make_toc_with_diagnostics<P>(
+    file_path_in: P,
+    cnf: Config,
+) -> Result<(String, Vec<Diagnostic>), Error>
 where
     P: AsRef<Path>,
 {
-    let content = read_file(file_path_in)?;
-    let config = parse_json_config_or_use_provided(&content, cnf);
+    let (content, config, mut diagnostics) = read_with_config(file_path_in, cnf)?;
+    check_markers(&content)?;
+    diagnostics.extend(marker_diagnostics(&content));
+    diagnostics.extend(heading_diagnostics(&content, &config));
 
     // create new ToC
     let new_toc = generate_toc(content.to_owned(), config);
 
     let res = add_toc(content, new_toc);
 
-    Ok(res)
+    Ok((res, diagnostics))
+}
+
+/// takes a file path and returns the outline of the file with the config used for it
+///
+/// The outline contains the same entries [`make_toc`] would add to the file, with
+/// the inline config applied and the headings renumbered if that is enabled.
+pub fn make_outline<P>(file_path_in: P, cnf: Config) -> Result<Outline, Error>
+where
+    P: AsRef<Path>,
+{
+    let (content, config, mut diagnostics) = read_with_config(file_path_in, cnf)?;
+    diagnostics.extend(marker_diagnostics(&content));
+    diagnostics.extend(heading_diagnostics(&content, &config));
+    let headings = TocTree::new(parse_headings(&content, &config), &config);
+
+    Ok(Outline {
+        config,
+        headings,
+        diagnostics,
+    })
+}
+
+/// reads the file and returns its content with the config to use, the inline config
+/// if there is one, and the warnings about the config. The template file is loaded
+/// and the headings are renumbered if set.
+fn read_with_config<P>(
+    file_path_in: P,
+    cnf: Config,
+) -> Result<(String, Config, Vec<Diagnostic>), Error>
+where
+    P: AsRef<Path>,
+{
+    let mut content = read_file(&file_path_in)?;
+    let mut config = parse_json_config_or_use_provided(&content, cnf)?;
+    // warnings about the inline config point to its line
+    let config_line = inline_config_json(&content).map(|(_, offset)| line_number(&content, offset));
+    let mut diagnostics: Vec<Diagnostic> = unknown_keys(&content)
+        .into_iter()
+        .map(|(key, offset)| {
+            Diagnostic::warning(
+                unknown_key_message(&key),
+                Some(line_number(&content, offset)),
+            )
+        })
+        .collect();
+    diagnostics.extend(config.diagnostics(config_line));
+    if let Some(template_file) = &config.template_file {
+        let dir = file_path_in.as_ref().parent().unwrap_or(Path::new(""));
+        config.template = Some(Template::from_file(dir.join(template_file))?);
+    }
+    if config.renumber_headings {
+        content = renumber_headings(content, config.clone());
+    }
+
+    Ok((content, config, diagnostics))
+}
+
+/// returns warnings about begin and end markers besides the ones the ToC is added between
+fn marker_diagnostics(content: &str) -> Vec<Diagnostic> {
+    let markers = find_markers(content);
+    let begin = markers.as_ref().map(|markers| markers.begin.start);
+    let end = markers
+        .as_ref()
+        .and_then(|markers| markers.end.as_ref())
+        .map(|end| end.start);
+
+    parser::scan(content)
+        .html_lines
+        .iter()
+        .filter_map(|line| {
+            let marker = if content[line.start..].starts_with("<!-- BEGIN mktoc") {
+                (Some(line.start) != begin).then_some(COMMENT_BEGIN)
+            } else if content[line.start..].starts_with(COMMENT_END) {
+                (Some(line.start) != end).then_some(COMMENT_END)
+            } else {
+                None
+            }?;
+            Some(Diagnostic::warning(
+                format!("additional {} marker ignored", marker),
+                Some(line_number(content, line.start)),
+            ))
+        })
+        .collect()
+}
+
+/// returns a note for every heading in the ToC that is more than one level deeper
+/// than the previous one, like a level 4 heading below a level 2 heading
+fn heading_diagnostics(content: &str, config: &Config) -> Vec<Diagnostic> {
+    parse_headings(content, config)
+        .windows(2)
+        .filter(|pair| pair[1].level > pair[0].level + 1)
+        .map(|pair| {
+            Diagnostic::info(
+                format!(
+                    "heading level {} follows level {}, level {} is skipped",
+                    pair[1].level,
+                    pair[0].level,
+                    pair[0].level + 1
+                ),
+                Some(pair[1].line),
+            )
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -322,10 +952,10 @@ mod tests {
             dbg!(test.name);
             match read_file(test.input) {
                 Ok(_content) => {
-                    assert_eq!(false, test.expect_error)
+                    assert!(!test.expect_error)
                 }
                 Err(_err) => {
-                    assert_eq!(true, test.expect_error)
+                    assert!(test.expect_error)
                 }
             }
         }
@@ -351,8 +981,8 @@ mod tests {
                 expected: r#"<!-- BEGIN mktoc -->
 
 - [Test](#test)
-- [Hello](#hello)
-  - [World](#world)
+  - [Hello](#hello)
+    - [World](#world)
 <!-- END mktoc -->"#,
             },
             TestCase {
@@ -369,11 +999,11 @@ mod tests {
                 expected: r#"<!-- BEGIN mktoc -->
 
 - [Test 1](#test-1)
-- [Test 2](#test-2)
-  - [Test 3](#test-3)
-    - [Test 4](#test-4)
-      - [Test 5](#test-5)
-        - [Test 6](#test-6)
+  - [Test 2](#test-2)
+    - [Test 3](#test-3)
+      - [Test 4](#test-4)
+        - [Test 5](#test-5)
+          - [Test 6](#test-6)
 <!-- END mktoc -->"#,
             },
             TestCase {
@@ -387,8 +1017,8 @@ mod tests {
                 expected: r#"<!-- BEGIN mktoc -->
 
 - [Test](#test)
-- [`Hello`](#hello)
-  - [World](#world)
+  - [`Hello`](#hello)
+    - [World](#world)
 <!-- END mktoc -->"#,
             },
             TestCase {
@@ -402,8 +1032,8 @@ mod tests {
                 expected: r#"<!-- BEGIN mktoc -->
 
 - [Test](#test)
-- [Hello 🥳](#hello-🥳)
-  - [World](#world)
+  - [Hello 🥳](#hello-)
+    - [World](#world)
 <!-- END mktoc -->"#,
             },
             TestCase {
@@ -424,7 +1054,7 @@ fn some_func() -> bool {}
                 expected: r#"<!-- BEGIN mktoc -->
 
 - [Test](#test)
-- [Hello](#hello)
+  - [Hello](#hello)
 <!-- END mktoc -->"#,
             },
             TestCase {
@@ -441,53 +1071,944 @@ fn some_func() -> bool {}
                 expected: r#"<!-- BEGIN mktoc -->
 
 - [Test duplicate headings](#test-duplicate-headings)
-- [Hello](#hello)
-- [Lorem](#lorem)
-  - [Lorem Ipsum](#lorem-ipsum)
-  - [Lorem](#lorem-1)
+  - [Hello](#hello)
+  - [Lorem](#lorem)
+    - [Lorem Ipsum](#lorem-ipsum)
+    - [Lorem](#lorem-1)
 <!-- END mktoc -->"#,
             },
-        ];
+            TestCase {
+                name: "Can parse indented headings and closing sequences",
+                input: r#"
+# Test ##
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+  ## Hello ###
+   ### World
+    #### Code
+"#,
+                expected: r#"<!-- BEGIN mktoc -->
 
-        for test in tests {
-            dbg!(test.name);
-            let new_toc = generate_toc(test.input.to_string(), Config::default());
-            assert_eq!(new_toc, test.expected.to_string());
-        }
-    }
+- [Test](#test)
+  - [Hello](#hello)
+    - [World](#world)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can parse Setext headings",
+                input: r#"---
+title: Front matter
+---
 
-    #[test]
-    fn test_generate_toc_wrap_details() {
-        struct TestCase<'a> {
-            name: &'a str,
-            input: &'a str,
-            expected: &'a str,
-        }
+Test
+====
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+Hello
+-----
 
-        let tests = [TestCase {
-            name: "Can wrap ToC in details",
-            input: r#"
+---
+
+### World"#,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#test)
+  - [Hello](#hello)
+    - [World](#world)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can exclude commented out sections",
+                input: r#"
 # Test
 <!-- BEGIN mktoc -->
 <!-- END mktoc -->
+<!--
+## Old section
+
+### Old subsection
+-->
 ## Hello
+  <!-- ## Inline -->
+"#,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#test)
+  - [Hello](#hello)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can parse HTML headings",
+                input: r#"
+<h1 align="center">Test</h1>
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+<h2 id="install">Installation</h2>
+
 ### World"#,
-            expected: r#"<!-- BEGIN mktoc -->
-<details><summary>Table of Contents</summary>
+                expected: r#"<!-- BEGIN mktoc -->
 
 - [Test](#test)
-- [Hello](#hello)
-  - [World](#world)
+  - [Installation](#install)
+    - [World](#world)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can use explicit heading ids",
+                input: r#"
+# Test {#top}
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+## Install
+## Installation {#install .big}
+### World {.unnumbered}"#,
+                expected: r#"<!-- BEGIN mktoc -->
 
-</details>
+- [Test](#top)
+  - [Install](#install-1)
+  - [Installation](#install)
+    - [World](#world)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can exclude HTML blocks",
+                input: r#"
+# Test
+<!-- BEGIN mktoc -->
 <!-- END mktoc -->
+<div>
+# Not a heading
+</div>
+
+## Hello
 "#,
-        }];
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#test)
+  - [Hello](#hello)
+<!-- END mktoc -->"#,
+            },
+        ];
 
         for test in tests {
             dbg!(test.name);
-            let new_toc = generate_toc(
-                test.input.to_string(),
+            let new_toc = generate_toc(test.input.to_string(), Config::default());
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_files() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: &'a str,
+        }
+
+        let tests = [
+            TestCase {
+                name: "Can find Setext headings",
+                input: "tests/files/README_12_setext-headings.md",
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#test)
+- [Setext level 1](#setext-level-1)
+  - [Setext level 2](#setext-level-2)
+  - [ATX heading](#atx-heading)
+  - [Setext headings can span multiple lines](#setext-headings-can-span-multiple-lines)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can exclude fenced code blocks",
+                input: "tests/files/README_13_fenced-code-blocks.md",
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Fenced code blocks](#fenced-code-blocks)
+  - [Tilde fences](#tilde-fences)
+  - [Fences wrapping fences](#fences-wrapping-fences)
+  - [Fences in list items](#fences-in-list-items)
+  - [Closing fences](#closing-fences)
+    - [End of file](#end-of-file)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can exclude indented code blocks",
+                input: "tests/files/README_14_indented-code-blocks.md",
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Indented code blocks](#indented-code-blocks)
+  - [Indented code](#indented-code)
+  - [Paragraph continuation](#paragraph-continuation)
+  - [List items](#list-items)
+    - [End of file](#end-of-file)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can exclude HTML comments and blocks",
+                input: "tests/files/README_15_html-comments.md",
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [HTML comments and blocks](#html-comments-and-blocks)
+  - [Commented out section](#commented-out-section)
+  - [HTML blocks](#html-blocks)
+    - [Inside details](#inside-details)
+  - [Marker example](#marker-example)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can parse HTML headings",
+                input: "tests/files/README_16_html-headings.md",
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [HTML headings](#html-headings)
+  - [Installation](#install)
+  - [Usage](#usage)
+    - [Markdown headings](#markdown-headings)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can handle collisions of duplicate anchors",
+                input: "tests/files/README_17_duplicate-slug-collisions.md",
+                expected: r#"<!-- BEGIN mktoc {"min_depth": 2} -->
+
+- [Foo](#foo)
+- [Foo](#foo-1)
+- [Foo 1](#foo-1-1)
+- [Bar 1](#bar-1)
+- [Bar](#bar)
+- [Bar](#bar-2)
+- [Baz](#baz-1)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can strip images and all kinds of links",
+                input: "tests/files/README_18_links-in-headings.md",
+                expected: r#"<!-- BEGIN mktoc {"min_depth": 2} -->
+
+- [Project](#project)
+- [The Rust language](#the-rust-language)
+- [Using foo [bar]](#using-foo-bar)
+- [See the docs and Examples](#see-the-docs-and-examples)
+- [Changelog](#changelog)
+- [Unknown [reference][nope]](#unknown-referencenope)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can leave out ignored and excluded headings",
+                input: "tests/files/README_19_ignored-headings.md",
+                expected: r#"<!-- BEGIN mktoc {"min_depth": 2, "exclude": ["License", "/^v\\d+\\./"]} -->
+
+- [Usage](#usage)
+- [Changelog](#changelog)
+  - [Unreleased](#unreleased)
+- [Usage](#usage-1)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can use labels instead of the heading text",
+                input: "tests/files/README_20_toc-labels.md",
+                expected: r#"<!-- BEGIN mktoc {"min_depth": 2} -->
+
+- [Setup](#how-to-set-up-the-project-on-your-machine)
+- [`cargo test`](#running-the-tests-with-cargo-test)
+- [Setup](#setup)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Can render with a template",
+                input: "tests/files/README_22_template.md",
+                expected: r#"<!-- BEGIN mktoc {"min_depth": 2, "template": {"header": "**Contents**\n", "entry": "{indent}* {number} [{text}](#{anchor})"}} -->
+
+**Contents**
+
+* 1. [Install](#install)
+  * 1.1. [Cargo](#cargo)
+* 2. [Usage](#usage)
+
+<!-- END mktoc -->"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let content = read_file(test.input).unwrap();
+            let config = parse_json_config_or_use_provided(&content, Config::default()).unwrap();
+            let new_toc = generate_toc(content, config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_slug_style() {
+        struct TestCase<'a> {
+            name: &'a str,
+            slug_style: SlugStyle,
+            expected: &'a str,
+        }
+
+        let input = r#"
+# Test
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+## Hello, World!
+## Hello, World!"#;
+
+        let tests = [
+            TestCase {
+                name: "GitHub",
+                slug_style: SlugStyle::GitHub,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#test)
+  - [Hello, World!](#hello-world)
+  - [Hello, World!](#hello-world-1)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Bitbucket",
+                slug_style: SlugStyle::Bitbucket,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#markdown-header-test)
+  - [Hello, World!](#markdown-header-hello-world)
+  - [Hello, World!](#markdown-header-hello-world_1)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Azure DevOps",
+                slug_style: SlugStyle::AzureDevOps,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Test](#test)
+  - [Hello, World!](#hello%2C-world%21)
+  - [Hello, World!](#hello%2C-world%21-1)
+<!-- END mktoc -->"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let new_toc = generate_toc(
+                input.to_string(),
+                Config {
+                    slug_style: test.slug_style,
+                    ..Config::default()
+                },
+            );
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_inline_formatting() {
+        struct TestCase<'a> {
+            name: &'a str,
+            strip_formatting: bool,
+            expected: &'a str,
+        }
+
+        let input = r#"
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+## **New** ![badge](x.svg) <sup>beta</sup>
+## The `config.toml` file
+## Q&amp;A
+## snake_case and *emphasis*"#;
+
+        let tests = [
+            TestCase {
+                name: "Keeps inline code and emphasis",
+                strip_formatting: false,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [**New** beta](#new--beta)
+- [The `config.toml` file](#the-configtoml-file)
+- [Q&amp;A](#qa)
+- [snake_case and *emphasis*](#snake_case-and-emphasis)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Strips all formatting",
+                strip_formatting: true,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [New beta](#new--beta)
+- [The config.toml file](#the-configtoml-file)
+- [Q&A](#qa)
+- [snake_case and emphasis](#snake_case-and-emphasis)
+<!-- END mktoc -->"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let config = Config {
+                strip_formatting: test.strip_formatting,
+                ..Default::default()
+            };
+            let new_toc = generate_toc(input.to_string(), config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_indentation() {
+        struct TestCase<'a> {
+            name: &'a str,
+            config: Config,
+            expected: &'a str,
+        }
+
+        let input = r#"
+# Title
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+## Install
+#### Skipped level
+### Cargo
+##### Deep
+## Usage"#;
+
+        let tests = [
+            TestCase {
+                name: "Nests by heading hierarchy",
+                config: Config::default(),
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Title](#title)
+  - [Install](#install)
+    - [Skipped level](#skipped-level)
+    - [Cargo](#cargo)
+      - [Deep](#deep)
+  - [Usage](#usage)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Shallowest included level is at the root",
+                config: Config {
+                    min_depth: 3,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Skipped level](#skipped-level)
+- [Cargo](#cargo)
+  - [Deep](#deep)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Legacy indentation by absolute level",
+                config: Config {
+                    legacy_indent: true,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Title](#title)
+- [Install](#install)
+    - [Skipped level](#skipped-level)
+  - [Cargo](#cargo)
+      - [Deep](#deep)
+- [Usage](#usage)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Legacy indentation wrapped in details",
+                config: Config {
+                    legacy_indent: true,
+                    min_depth: 4,
+                    wrap_in_details: true,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+<details><summary>Table of Contents</summary>
+
+- [Skipped level](#skipped-level)
+  - [Deep](#deep)
+
+</details>
+<!-- END mktoc -->
+"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let new_toc = generate_toc(input.to_string(), test.config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_ordered_indent() {
+        // entries below `10.` start one column further right than below `9.`
+        let mut input = String::from("<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n");
+        for i in 1..=10 {
+            input.push_str(&format!("## H{}\n", i));
+        }
+        input.push_str("### Child\n#### Grandchild\n");
+        let config = Config {
+            ordered: true,
+            ..Default::default()
+        };
+
+        let toc = generate_toc(input, config);
+        assert!(toc.contains("\n9. [H9](#h9)\n10. [H10](#h10)\n    1. [Child](#child)\n       1. [Grandchild](#grandchild)\n"));
+    }
+
+    #[test]
+    fn test_generate_toc_list_style() {
+        struct TestCase<'a> {
+            name: &'a str,
+            config: Config,
+            expected: &'a str,
+        }
+
+        let input = r#"
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+## Install
+### Cargo
+#### Features
+### Binary
+## Usage
+### CLI"#;
+
+        let tests = [
+            TestCase {
+                name: "Asterisk bullets with four spaces",
+                config: Config {
+                    bullet: Bullet::Asterisk,
+                    indent: Some(Indent::Spaces(4)),
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+* [Install](#install)
+    * [Cargo](#cargo)
+        * [Features](#features)
+    * [Binary](#binary)
+* [Usage](#usage)
+    * [CLI](#cli)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Ordered list",
+                config: Config {
+                    ordered: true,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+1. [Install](#install)
+   1. [Cargo](#cargo)
+      1. [Features](#features)
+   2. [Binary](#binary)
+2. [Usage](#usage)
+   1. [CLI](#cli)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Tab indentation",
+                config: Config {
+                    bullet: Bullet::Plus,
+                    indent: Some(Indent::Tab),
+                    max_depth: 3,
+                    ..Default::default()
+                },
+                expected: "<!-- BEGIN mktoc -->\n\n+ [Install](#install)\n\t+ [Cargo](#cargo)\n\t+ [Binary](#binary)\n+ [Usage](#usage)\n\t+ [CLI](#cli)\n<!-- END mktoc -->",
+            },
+            TestCase {
+                name: "Wrapped in details with four spaces",
+                config: Config {
+                    indent: Some(Indent::Spaces(4)),
+                    wrap_in_details: true,
+                    min_depth: 3,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+<details><summary>Table of Contents</summary>
+
+- [Cargo](#cargo)
+    - [Features](#features)
+- [Binary](#binary)
+- [CLI](#cli)
+
+</details>
+<!-- END mktoc -->
+"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let new_toc = generate_toc(input.to_string(), test.config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_numbering_legacy_indent() {
+        let input = "<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n### Intro\n## Guide\n#### Install";
+        let config = Config {
+            legacy_indent: true,
+            numbering: Some(Numbering::Alpha),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            generate_toc(input.to_string(), config),
+            "<!-- BEGIN mktoc -->\n\n  - [A Intro](#intro)\n- [A Guide](#guide)\n    - [A.1 Install](#install)\n<!-- END mktoc -->"
+        );
+    }
+
+    #[test]
+    fn test_generate_toc_numbering() {
+        struct TestCase<'a> {
+            name: &'a str,
+            config: Config,
+            expected: &'a str,
+        }
+
+        let input = r#"
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+# Guide
+## Install
+### Cargo
+### Binary
+## Usage
+# Appendix
+## License"#;
+
+        let tests = [
+            TestCase {
+                name: "Decimal numbers with trailing dot",
+                config: Config {
+                    numbering: Some(Numbering::DecimalDot),
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [1. Guide](#guide)
+  - [1.1. Install](#install)
+    - [1.1.1. Cargo](#cargo)
+    - [1.1.2. Binary](#binary)
+  - [1.2. Usage](#usage)
+- [2. Appendix](#appendix)
+  - [2.1. License](#license)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Roman numerals over the included range",
+                config: Config {
+                    numbering: Some(Numbering::Roman),
+                    min_depth: 2,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [I Install](#install)
+  - [I.1 Cargo](#cargo)
+  - [I.2 Binary](#binary)
+- [II Usage](#usage)
+- [III License](#license)
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Restart below top level entries",
+                config: Config {
+                    numbering: Some(Numbering::Alpha),
+                    restart_numbering: true,
+                    ..Default::default()
+                },
+                expected: r#"<!-- BEGIN mktoc -->
+
+- [Guide](#guide)
+  - [A Install](#install)
+    - [A.1 Cargo](#cargo)
+    - [A.2 Binary](#binary)
+  - [B Usage](#usage)
+- [Appendix](#appendix)
+  - [A License](#license)
+<!-- END mktoc -->"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let new_toc = generate_toc(input.to_string(), test.config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_template_text_html() {
+        let input = "<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n## `Vec<T>` & *Friends*";
+        let config = Config {
+            template: Some(Template {
+                entry: String::from("<td>{text_html}</td> <td>{text}</td>"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            generate_toc(input.to_string(), config),
+            "<!-- BEGIN mktoc -->\n\n<td>Vec&lt;T&gt; &amp; Friends</td> <td>`Vec<T>` & *Friends*</td>\n\n<!-- END mktoc -->"
+        );
+    }
+
+    #[test]
+    fn test_generate_toc_template() {
+        struct TestCase<'a> {
+            name: &'a str,
+            template: Template,
+            wrap_in_details: bool,
+            expected: &'a str,
+        }
+
+        let input = r#"
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+# Guide
+## Install
+### Cargo
+## Usage"#;
+
+        let tests = [
+            TestCase {
+                name: "Table with header",
+                template: Template {
+                    header: String::from("| Section | Level |\n| --- | --- |"),
+                    entry: String::from("| {number} [{text}](#{anchor}) | {level} |"),
+                    footer: String::new(),
+                },
+                wrap_in_details: false,
+                expected: r#"<!-- BEGIN mktoc -->
+
+| Section | Level |
+| --- | --- |
+| 1. [Guide](#guide) | 1 |
+| 1.1. [Install](#install) | 2 |
+| 1.1.1. [Cargo](#cargo) | 3 |
+| 1.2. [Usage](#usage) | 2 |
+
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "Emoji bullets",
+                template: Template {
+                    entry: String::from("{indent}- 📄 [{text}](#{anchor})"),
+                    ..Default::default()
+                },
+                wrap_in_details: false,
+                expected: r#"<!-- BEGIN mktoc -->
+
+- 📄 [Guide](#guide)
+  - 📄 [Install](#install)
+    - 📄 [Cargo](#cargo)
+  - 📄 [Usage](#usage)
+
+<!-- END mktoc -->"#,
+            },
+            TestCase {
+                name: "HTML with header and footer",
+                template: Template {
+                    header: String::from("<nav class=\"toc\">"),
+                    entry: String::from(
+                        "  <a class=\"depth-{depth}\" href=\"#{anchor}\">{index}. {text}</a>",
+                    ),
+                    footer: String::from("</nav>"),
+                },
+                wrap_in_details: false,
+                expected: r##"<!-- BEGIN mktoc -->
+
+<nav class="toc">
+  <a class="depth-0" href="#guide">1. Guide</a>
+  <a class="depth-1" href="#install">1. Install</a>
+  <a class="depth-2" href="#cargo">1. Cargo</a>
+  <a class="depth-1" href="#usage">2. Usage</a>
+</nav>
+
+<!-- END mktoc -->"##,
+            },
+            TestCase {
+                name: "Literal braces and details",
+                template: Template {
+                    entry: String::from("{indent}{marker} [{text}](#{anchor}) {{{level}}}"),
+                    ..Default::default()
+                },
+                wrap_in_details: true,
+                expected: r#"<!-- BEGIN mktoc -->
+<details><summary>Table of Contents</summary>
+
+- [Guide](#guide) {1}
+  - [Install](#install) {2}
+    - [Cargo](#cargo) {3}
+  - [Usage](#usage) {2}
+
+</details>
+<!-- END mktoc -->
+"#,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let config = Config {
+                template: Some(test.template),
+                wrap_in_details: test.wrap_in_details,
+                ..Default::default()
+            };
+            let new_toc = generate_toc(input.to_string(), config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_html() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            config: Config,
+            expected: &'a str,
+        }
+
+        let input = r#"
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+# Guide
+## Install
+### Cargo
+## Usage & *Tips*
+# Appendix"#;
+
+        let tests = [
+            TestCase {
+                name: "Nested lists",
+                input,
+                config: Config {
+                    html: true,
+                    ..Default::default()
+                },
+                expected: r##"<!-- BEGIN mktoc -->
+
+<ul>
+  <li><a href="#guide">Guide</a>
+    <ul>
+      <li><a href="#install">Install</a>
+        <ul>
+          <li><a href="#cargo">Cargo</a></li>
+        </ul>
+      </li>
+      <li><a href="#usage--tips">Usage &amp; Tips</a></li>
+    </ul>
+  </li>
+  <li><a href="#appendix">Appendix</a></li>
+</ul>
+
+<!-- END mktoc -->"##,
+            },
+            TestCase {
+                name: "Ordered and numbered lists in details",
+                input,
+                config: Config {
+                    html: true,
+                    ordered: true,
+                    numbering: Some(Numbering::Decimal),
+                    wrap_in_details: true,
+                    max_depth: 2,
+                    ..Default::default()
+                },
+                expected: r##"<!-- BEGIN mktoc -->
+<details><summary>Table of Contents</summary>
+
+<ol>
+  <li><a href="#guide">1 Guide</a>
+    <ol>
+      <li><a href="#install">1.1 Install</a></li>
+      <li><a href="#usage--tips">1.2 Usage &amp; Tips</a></li>
+    </ol>
+  </li>
+  <li><a href="#appendix">2 Appendix</a></li>
+</ol>
+
+</details>
+<!-- END mktoc -->
+"##,
+            },
+            TestCase {
+                name: "Skipped levels and escaping",
+                input: r#"
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+### `<main>` element
+# Title {#"quoted"}
+### Sub"#,
+                config: Config {
+                    html: true,
+                    legacy_indent: true,
+                    ..Default::default()
+                },
+                expected: r##"<!-- BEGIN mktoc -->
+
+<ul>
+  <li>
+    <ul>
+      <li><a href="#main-element">&lt;main&gt; element</a></li>
+    </ul>
+  </li>
+  <li><a href="#&quot;quoted&quot;">Title</a>
+    <ul>
+      <li><a href="#sub">Sub</a></li>
+    </ul>
+  </li>
+</ul>
+
+<!-- END mktoc -->"##,
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let new_toc = generate_toc(test.input.to_string(), test.config);
+            assert_eq!(new_toc, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_generate_toc_wrap_details() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: &'a str,
+        }
+
+        let tests = [TestCase {
+            name: "Can wrap ToC in details",
+            input: r#"
+# Test
+<!-- BEGIN mktoc -->
+<!-- END mktoc -->
+## Hello
+### World"#,
+            expected: r#"<!-- BEGIN mktoc -->
+<details><summary>Table of Contents</summary>
+
+- [Test](#test)
+  - [Hello](#hello)
+    - [World](#world)
+
+</details>
+<!-- END mktoc -->
+"#,
+        }];
+
+        for test in tests {
+            dbg!(test.name);
+            let new_toc = generate_toc(
+                test.input.to_string(),
                 Config {
                     wrap_in_details: true,
                     ..Config::default()
@@ -532,11 +2053,51 @@ fn some_func() -> bool {}
                     ..Default::default()
                 },
             },
+            TestCase {
+                name: "List style from inline config",
+                input: "<!-- BEGIN mktoc {\"bullet\": \"*\", \"ordered\": true, \"indent\": \"tab\"} -->",
+                input_cnf: Config::default(),
+                expected: Config {
+                    bullet: Bullet::Asterisk,
+                    ordered: true,
+                    indent: Some(Indent::Tab),
+                    start_comment: String::from(
+                        "<!-- BEGIN mktoc {\"bullet\": \"*\", \"ordered\": true, \"indent\": \"tab\"} -->",
+                    ),
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                name: "Slug style from inline config",
+                input: "<!-- BEGIN mktoc {\"slug_style\": \"gitlab\"} -->",
+                input_cnf: Config {
+                    slug_style: SlugStyle::Pandoc,
+                    ..Default::default()
+                },
+                expected: Config {
+                    slug_style: SlugStyle::GitLab,
+                    start_comment: String::from(
+                        "<!-- BEGIN mktoc {\"slug_style\": \"gitlab\"} -->",
+                    ),
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                name: "Config in front matter is ignored",
+                input: "---\nexample: <!-- BEGIN mktoc {\"max_depth\": 2} -->\n---\n",
+                input_cnf: Config {
+                    wrap_in_details: true,
+                    ..Default::default()
+                },
+                expected: Config {
+                    wrap_in_details: true,
+                    ..Default::default()
+                },
+            },
         ];
-
         for test in tests {
             dbg!(test.name);
-            let cnf = parse_json_config_or_use_provided(test.input, test.input_cnf);
+            let cnf = parse_json_config_or_use_provided(test.input, test.input_cnf).unwrap();
             assert_eq!(cnf, test.expected);
         }
     }
@@ -563,31 +2124,115 @@ fn some_func() -> bool {}
         let tests = [
             TestCase {
                 cnf1: Config {
-                    min_depth: 1,
+                    min_depth: 1,
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    min_depth: 2,
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    max_depth: 3,
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    max_depth: 4,
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    wrap_in_details: false,
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    wrap_in_details: true,
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    slug_style: SlugStyle::GitHub,
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    slug_style: SlugStyle::GitLab,
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    numbering: Some(Numbering::Decimal),
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    numbering: Some(Numbering::DecimalDot),
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    indent: None,
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    indent: Some(Indent::Spaces(2)),
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    exclude: vec![String::from("License")],
+                    ..Default::default()
+                },
+                cnf2: Config {
+                    exclude: vec![String::from("/^License$/")],
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config::default(),
+                cnf2: Config {
+                    html: true,
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config::default(),
+                cnf2: Config {
+                    template: Some(Template::default()),
+                    ..Default::default()
+                },
+            },
+            TestCase {
+                cnf1: Config {
+                    template_file: Some(String::from("toc.txt")),
                     ..Default::default()
                 },
                 cnf2: Config {
-                    min_depth: 2,
+                    template_file: Some(String::from("toc.html")),
                     ..Default::default()
                 },
             },
             TestCase {
                 cnf1: Config {
-                    max_depth: 3,
+                    strict: false,
                     ..Default::default()
                 },
                 cnf2: Config {
-                    max_depth: 4,
+                    strict: true,
                     ..Default::default()
                 },
             },
             TestCase {
                 cnf1: Config {
-                    wrap_in_details: false,
+                    strip_formatting: false,
                     ..Default::default()
                 },
                 cnf2: Config {
-                    wrap_in_details: true,
+                    strip_formatting: true,
                     ..Default::default()
                 },
             },
@@ -630,7 +2275,7 @@ fn some_func() -> bool {}
             TestCase {
                 name: "Case 03: Emoji 💃",
                 input: "Emoji 💃",
-                expected: String::from("emoji-💃"),
+                expected: String::from("emoji-"),
             },
             TestCase {
                 name: "Case 03: Number sign",
@@ -640,7 +2285,7 @@ fn some_func() -> bool {}
             TestCase {
                 name: "Case 04: Special signs",
                 input: "Reasons to say !@;#\\!! out loud",
-                expected: String::from("reasons-to-say-@-out-loud"),
+                expected: String::from("reasons-to-say--out-loud"),
             },
             TestCase {
                 name: "Case 05: Empty",
@@ -655,118 +2300,683 @@ fn some_func() -> bool {}
         }
     }
 
+    #[test]
+    fn test_unique_anchor() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: Vec<&'a str>,
+            expected: Vec<&'a str>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "repeated slugs are numbered",
+                input: vec!["foo", "foo", "foo"],
+                expected: vec!["foo", "foo-1", "foo-2"],
+            },
+            TestCase {
+                name: "numbered slug collides with a later heading",
+                input: vec!["foo", "foo", "foo-1"],
+                expected: vec!["foo", "foo-1", "foo-1-1"],
+            },
+            TestCase {
+                name: "numbered slug collides with an earlier heading",
+                input: vec!["foo-1", "foo", "foo"],
+                expected: vec!["foo-1", "foo", "foo-2"],
+            },
+            TestCase {
+                name: "empty slugs",
+                input: vec!["", ""],
+                expected: vec!["", "-1"],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let mut found_headings = HashMap::new();
+            let anchors: Vec<String> = test
+                .input
+                .into_iter()
+                .map(|slug| {
+                    unique_anchor(&mut found_headings, slug.to_string(), SlugStyle::GitHub).0
+                })
+                .collect();
+            assert_eq!(anchors, test.expected);
+        }
+    }
+
+    #[test]
+    fn test_text_to_url_github_anchors() {
+        // headings and the anchors GitHub renders for them
+        let tests = [
+            ("Über uns", "über-uns"),
+            ("Ελληνικά", "ελληνικά"),
+            ("ΣΊΣΥΦΟΣ", "σίσυφος"),
+            ("Русский язык", "русский-язык"),
+            ("日本語の見出し", "日本語の見出し"),
+            ("Café — notes", "café--notes"),
+            ("Cafe\u{301} au lait", "cafe\u{301}-au-lait"),
+            ("mktoc™ is fast", "mktoc-is-fast"),
+            ("← Back to top", "-back-to-top"),
+            ("🎉 Features", "-features"),
+            ("x² + y²", "x--y"),
+            ("C++ & Rust", "c--rust"),
+            ("What's new?", "whats-new"),
+            ("1.2.3 Release", "123-release"),
+            ("snake_case and kebab-case", "snake_case-and-kebab-case"),
+            ("«Quotes» and “quotes”", "quotes-and-quotes"),
+            ("Tabs\tare removed", "tabsare-removed"),
+        ];
+
+        for (input, expected) in tests {
+            dbg!(input);
+            assert_eq!(text_to_url(input), expected);
+        }
+    }
+
     #[test]
     fn test_parse_json_config() {
         struct TestCase<'a> {
             name: &'a str,
             input: &'a str,
-            expected: Config,
+            expected: Result<Config, &'a str>,
         }
 
         let tests = [
             TestCase {
                 name: "only min_depth set",
                 input: "<!-- BEGIN mktoc {\"min_depth\":3} -->",
-                expected: Config {
+                expected: Ok(Config {
                     min_depth: 3,
                     ..Default::default()
-                },
+                }),
             },
             TestCase {
                 name: "min_depth and max_depth set",
                 input: "<!-- BEGIN mktoc {\"min_depth\":3, \"max_depth\":4} -->",
-                expected: Config {
+                expected: Ok(Config {
                     min_depth: 3,
                     max_depth: 4,
                     ..Default::default()
-                },
+                }),
             },
             TestCase {
                 name: "only max_depth set",
                 input: "<!-- BEGIN mktoc {\"max_depth\":4} -->",
-                expected: Config {
+                expected: Ok(Config {
                     max_depth: 4,
                     ..Default::default()
-                },
+                }),
             },
             TestCase {
                 name: "no json config, returns default values",
                 input: "<!-- BEGIN mktoc -->",
-                expected: Config::default(),
+                expected: Ok(Config::default()),
             },
             TestCase {
-                name: "invalid max_depth set results in default max_depth being used",
+                name: "invalid max_depth set results in an error",
                 input: "<!-- BEGIN mktoc {\"max_depth\":10} -->",
-                expected: Config {
-                    max_depth: 6,
-                    ..Default::default()
-                },
+                expected: Err("max_depth of 10 out of bound, expected 1 to 6"),
             },
             TestCase {
-                name: "invalid min_depth set results in default min_depth being used",
+                name: "invalid min_depth set results in an error",
                 input: "<!-- BEGIN mktoc {\"min_depth\":-1} -->",
-                expected: Config {
-                    max_depth: 6,
-                    ..Default::default()
-                },
+                expected: Err("min_depth of -1 out of bound, expected 1 to 6"),
             },
             TestCase {
-                name: "invalid config used, fallback to default",
+                name: "unknown keys are allowed without strict",
                 input: "<!-- BEGIN mktoc {\"doesn_t_exists\":false} -->",
-                expected: Config {
-                    max_depth: 6,
+                expected: Ok(Config::default()),
+            },
+            TestCase {
+                name: "invalid JSON results in an error with its position",
+                input: "# Test\n\n<!-- BEGIN mktoc {\"max_depth\": 3,} -->",
+                expected: Err("invalid inline config in line 3 column 34: trailing comma"),
+            },
+            TestCase {
+                name: "invalid JSON with non-ASCII characters",
+                input: "<!-- BEGIN mktoc {\"ééé -->",
+                expected: Err(
+                    "invalid inline config in line 1 column 22: EOF while parsing a string",
+                ),
+            },
+            TestCase {
+                name: "position after non-ASCII characters is counted in characters",
+                input: "<!-- BEGIN mktoc {\"exclude\": [\"Über\"],} -->",
+                expected: Err("invalid inline config in line 1 column 39: trailing comma"),
+            },
+            TestCase {
+                name: "wrong type results in an error",
+                input: "<!-- BEGIN mktoc {\"max_depth\": \"3\"} -->",
+                expected: Err(
+                    "invalid inline config in line 1 column 34: invalid value for `max_depth`: invalid type: string \"3\", expected i32",
+                ),
+            },
+            TestCase {
+                name: "min_depth greater than max_depth results in an error",
+                input: "<!-- BEGIN mktoc {\"min_depth\": 4, \"max_depth\": 2} -->",
+                expected: Err("min_depth of 4 is greater than max_depth of 2"),
+            },
+            TestCase {
+                name: "unknown keys result in an error with strict",
+                input: "<!-- BEGIN mktoc {\"strict\": true,\n  \"max_dpeth\": 3} -->",
+                expected: Err(
+                    "invalid inline config in line 2 column 3: unknown key `max_dpeth`, did you mean `max_depth`?",
+                ),
+            },
+            TestCase {
+                name: "unknown keys are found by their position and not in values",
+                input: "<!-- BEGIN mktoc {\"strict\": true, \"exclude\": [\"max_dpeth\"],\n  \"max_dpeth\": 3} -->",
+                expected: Err(
+                    "invalid inline config in line 2 column 3: unknown key `max_dpeth`, did you mean `max_depth`?",
+                ),
+            },
+            TestCase {
+                name: "unknown keys without similar option",
+                input: "<!-- BEGIN mktoc {\"strict\": true, \"foo\": 3} -->",
+                expected: Err("invalid inline config in line 1 column 35: unknown key `foo`"),
+            },
+        ];
+
+        for test in tests {
+            // logs the name of the test in case it fails.
+            dbg!(test.name);
+            let cnf = parse_json_config_or_use_provided(test.input, Config::default());
+            match (cnf, test.expected) {
+                (Ok(cnf), Ok(expected)) => {
+                    assert_eq!(cnf.max_depth, expected.max_depth);
+                    assert_eq!(cnf.min_depth, expected.min_depth);
+                    assert_eq!(&cnf.start_comment, test.input.lines().last().unwrap());
+                }
+                (Err(e), Err(expected)) => assert_eq!(e.to_string(), expected),
+                (cnf, expected) => panic!("expected {:?}, got {:?}", expected, cnf),
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_toc() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: &'a str,
+        }
+
+        let tests = [
+            TestCase {
+                name: "Replaces the content between the markers",
+                input: "# Test\n<!-- BEGIN mktoc -->\nold\n<!-- END mktoc -->\n",
+                expected: "# Test\nnew\n",
+            },
+            TestCase {
+                name: "Ignores markers inside front matter",
+                input: "---\nexample: |\n<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n---\n<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n",
+                expected: "---\nexample: |\n<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n---\nnew\n",
+            },
+            TestCase {
+                name: "Ignores markers inside code blocks",
+                input: "```html\n<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n```\n<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n",
+                expected: "```html\n<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n```\nnew\n",
+            },
+            TestCase {
+                name: "Replaces ToC wrapped in details",
+                input: "<!-- BEGIN mktoc -->\n<details><summary>Table of Contents</summary>\n\n- [Test](#test)\n\n</details>\n<!-- END mktoc -->\n# Test",
+                expected: "new\n# Test",
+            },
+            TestCase {
+                name: "Does nothing without markers",
+                input: "+++\ntitle = \"Test\"\n+++\n# Test\n",
+                expected: "+++\ntitle = \"Test\"\n+++\n# Test\n",
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let res = add_toc(test.input.to_string(), String::from("new"));
+            assert_eq!(res, test.expected.to_string());
+        }
+    }
+
+    #[test]
+    fn test_make_toc() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: &'a str,
+        }
+
+        let tests = [
+            TestCase {
+                name: "Replaces the ToC in the file",
+                input: "tests/files/README_01.md",
+                expected: "tests/files/README_01.md",
+            },
+            TestCase {
+                name: "Renders with a template file",
+                input: "tests/files/README_23_template-file.md",
+                expected: r#"# Test
+
+<!-- BEGIN mktoc {"min_depth": 2, "template_file": "toc-template.txt"} -->
+
+| Section | Level |
+| ------- | ----- |
+| [Install](#install) | 2 |
+| [Cargo](#cargo) | 3 |
+| [Usage](#usage) | 2 |
+
+<!-- END mktoc -->
+
+## Install
+
+### Cargo
+
+## Usage
+"#,
+            },
+            TestCase {
+                name: "Renders block HTML with a template",
+                input: "tests/files/README_24_nav-template.md",
+                expected: r##"# Title
+
+<!-- BEGIN mktoc {"template": {"header": "<nav>", "entry": "<a href=\"#{anchor}\">{text_html}</a>", "footer": "</nav>"}} -->
+
+<nav>
+<a href="#title">Title</a>
+<a href="#install">Install</a>
+<a href="#cargo">Cargo</a>
+<a href="#usage">Usage</a>
+</nav>
+
+<!-- END mktoc -->
+## Install
+
+### Cargo
+
+## Usage
+"##,
+            },
+            TestCase {
+                name: "Renders HTML lists",
+                input: "tests/files/README_25_html.md",
+                expected: r##"# Title
+
+<!-- BEGIN mktoc {"html": true} -->
+
+<ul>
+  <li><a href="#title">Title</a>
+    <ul>
+      <li><a href="#install">Install</a>
+        <ul>
+          <li><a href="#cargo">Cargo</a></li>
+        </ul>
+      </li>
+      <li><a href="#usage">Usage</a></li>
+    </ul>
+  </li>
+</ul>
+
+<!-- END mktoc -->
+## Install
+
+### Cargo
+
+## Usage
+"##,
+            },
+            TestCase {
+                name: "Renumbers the headings",
+                input: "tests/files/README_21_renumber-headings.md",
+                expected: r#"# Spec
+
+<!-- BEGIN mktoc {"min_depth": 2, "renumber_headings": true, "exclude": ["License"]} -->
+
+- [1. Overview](#1-overview)
+  - [1.1. Goals](#11-goals)
+  - [1.2. Non-goals](#12-non-goals)
+- [2. Design](#2-design)
+- [Setext headings are not renumbered](#setext-headings-are-not-renumbered)
+<!-- END mktoc -->
+
+## 1. Overview
+
+### 1.1. Goals
+
+### 1.2. Non-goals
+
+## 2. Design
+
+Setext headings are not renumbered
+----------------------------------
+
+## License
+"#,
+            },
+        ];
+
+        // the copies are run in their own directory with the template file, so
+        // relative template files are found
+        let dir = std::env::temp_dir().join(format!("mktoc-make-toc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy("tests/files/toc-template.txt", dir.join("toc-template.txt")).unwrap();
+
+        for test in tests {
+            dbg!(test.name);
+            let content = make_toc(test.input, Config::default()).unwrap();
+            let expected = match read_file(test.expected) {
+                Ok(file) => generate_and_add(file),
+                Err(_) => test.expected.to_string(),
+            };
+            assert_eq!(content, expected);
+
+            // running it again doesn't change anything
+            let path = dir.join(Path::new(test.input).file_name().unwrap());
+            std::fs::write(&path, &content).unwrap();
+            assert_eq!(make_toc(&path, Config::default()).unwrap(), content);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_make_toc_errors() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a [u8],
+            config: Config,
+            expected: &'a str,
+        }
+
+        let tests = [
+            TestCase {
+                name: "No markers",
+                input: b"# Test\n",
+                config: Config::default(),
+                expected: "no <!-- BEGIN mktoc --> and <!-- END mktoc --> markers found",
+            },
+            TestCase {
+                name: "Begin marker without end marker",
+                input: b"# Test\n\n<!-- BEGIN mktoc -->\n",
+                config: Config::default(),
+                expected: "unbalanced marker in line 3, expected <!-- BEGIN mktoc --> followed by <!-- END mktoc -->",
+            },
+            TestCase {
+                name: "End marker before begin marker",
+                input: b"<!-- END mktoc -->\n# Test\n<!-- BEGIN mktoc -->\n",
+                config: Config::default(),
+                expected: "unbalanced marker in line 3, expected <!-- BEGIN mktoc --> followed by <!-- END mktoc -->",
+            },
+            TestCase {
+                name: "End marker without begin marker",
+                input: b"# Test\n<!-- END mktoc -->\n",
+                config: Config::default(),
+                expected: "unbalanced marker in line 2, expected <!-- BEGIN mktoc --> followed by <!-- END mktoc -->",
+            },
+            TestCase {
+                name: "Invalid inline config",
+                input: b"<!-- BEGIN mktoc {\"max_depth\": 3 -->\n<!-- END mktoc -->\n",
+                config: Config::default(),
+                expected: "invalid inline config in line 1 column 32: EOF while parsing an object",
+            },
+            TestCase {
+                name: "Depth out of range",
+                input: b"<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n",
+                config: Config {
+                    max_depth: 7,
                     ..Default::default()
                 },
+                expected: "max_depth of 7 out of bound, expected 1 to 6",
+            },
+            TestCase {
+                name: "Not UTF-8",
+                input: b"<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n# Caf\xe9\n",
+                config: Config::default(),
+                expected: "invalid UTF-8 in line 3",
             },
         ];
 
         for test in tests {
-            // logs the name of the test in case it fails.
             dbg!(test.name);
-            let (cnf, _json_config_found) = parse_json_config(&test.input);
-            assert_eq!(cnf.max_depth, test.expected.max_depth);
-            assert_eq!(cnf.min_depth, test.expected.min_depth);
-            assert_eq!(cnf.start_comment, test.input.to_string());
+            let path = std::env::temp_dir().join(format!("mktoc-error-{}.md", std::process::id()));
+            std::fs::write(&path, test.input).unwrap();
+            let result = make_toc(&path, test.config);
+            std::fs::remove_file(path).unwrap();
+            assert_eq!(result.unwrap_err().to_string(), test.expected);
         }
+
+        assert!(matches!(
+            make_toc("tests/files/does-not-exist.md", Config::default()),
+            Err(Error::Io(_))
+        ));
     }
 
     #[test]
-    fn test_strip_markdown_links() {
-        struct TestCase {
-            input: &'static str,
-            expected: String,
+    fn test_make_toc_diagnostics() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            config: Config,
+            expected: Vec<&'a str>,
         }
-        let test_cases = [
+
+        let tests = [
             TestCase {
-                input: "This is a [link](https://example.com) in a Markdown text.",
-                expected: String::from("This is a link in a Markdown text."),
+                name: "No warnings",
+                input: "<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n# A\n## B\n",
+                config: Config::default(),
+                expected: vec![],
             },
             TestCase {
-                input: "This is a [link](https://example.com) and [another one](https://example.org) in a Markdown text.",
-                expected: String::from("This is a link and another one in a Markdown text."),
+                name: "Invalid exclude pattern",
+                input: "<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n# A\n",
+                config: Config {
+                    exclude: vec![String::from("/(/")],
+                    ..Default::default()
+                },
+                expected: vec!["WARNING: invalid exclude pattern '/(/' ignored"],
             },
             TestCase {
-                input: "This is a text without any links.",
-                expected: String::from("This is a text without any links."),
+                name: "Ignored options in inline config",
+                input: "# A\n\n<!-- BEGIN mktoc {\"html\": true, \"template\": {}, \"restart_numbering\": true} -->\n<!-- END mktoc -->\n",
+                config: Config::default(),
+                expected: vec![
+                    "WARNING: line 3: html ignored, the template takes precedence",
+                    "WARNING: line 3: restart_numbering ignored without numbering or renumber_headings",
+                ],
             },
             TestCase {
-                input: "",
-                expected: String::from(""),
+                name: "Unknown key in inline config",
+                input: "# A\n\n<!-- BEGIN mktoc {\"min_depth\": 1,\n \"wrap_in_detail\": true} -->\n<!-- END mktoc -->\n",
+                config: Config::default(),
+                expected: vec![
+                    "WARNING: line 4: unknown key `wrap_in_detail`, did you mean `wrap_in_details`?",
+                ],
+            },
+            TestCase {
+                name: "Additional markers",
+                input: "<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n# A\n\n<!-- END mktoc -->\n\n<!-- BEGIN mktoc -->\n",
+                config: Config::default(),
+                expected: vec![
+                    "WARNING: line 5: additional <!-- END mktoc --> marker ignored",
+                    "WARNING: line 7: additional <!-- BEGIN mktoc --> marker ignored",
+                ],
             },
             TestCase {
-                input: "This is a [link](https://example.com) with some text after it.",
-                expected: String::from("This is a link with some text after it."),
+                name: "Skipped heading level",
+                input: "<!-- BEGIN mktoc -->\n<!-- END mktoc -->\n# A\n### B\n## C\n#### D\n",
+                config: Config::default(),
+                expected: vec![
+                    "INFO: line 4: heading level 3 follows level 1, level 2 is skipped",
+                    "INFO: line 6: heading level 4 follows level 2, level 3 is skipped",
+                ],
             },
         ];
 
-        for test_case in &test_cases {
-            assert_eq!(strip_markdown_links(test_case.input), test_case.expected);
+        for test in tests {
+            dbg!(test.name);
+            let path =
+                std::env::temp_dir().join(format!("mktoc-diagnostics-{}.md", std::process::id()));
+            std::fs::write(&path, test.input).unwrap();
+            let result = make_toc_with_diagnostics(&path, test.config);
+            std::fs::remove_file(path).unwrap();
+            let (_, diagnostics) = result.unwrap();
+            assert_eq!(diagnostics.len(), test.expected.len());
+            for (diagnostic, expected) in diagnostics.iter().zip(test.expected) {
+                assert!(
+                    diagnostic.to_string().starts_with(expected),
+                    "{}",
+                    diagnostic
+                );
+            }
         }
     }
 
-    // TODO: implement this test
-    // #[test]
-    // fn test_make_toc() {
-    //     todo!()
-    // }
+    #[test]
+    fn test_make_outline() {
+        let outline = make_outline(
+            "tests/files/README_21_renumber-headings.md",
+            Config::default(),
+        )
+        .unwrap();
+
+        // the inline config is used and the headings are renumbered
+        assert_eq!(outline.config.min_depth, 2);
+        assert!(outline.config.renumber_headings);
+        let anchors: Vec<&str> = outline
+            .headings
+            .iter()
+            .map(|node| node.heading.anchor.as_str())
+            .collect();
+        assert_eq!(
+            anchors,
+            [
+                "1-overview",
+                "11-goals",
+                "12-non-goals",
+                "2-design",
+                "setext-headings-are-not-renumbered"
+            ]
+        );
+
+        let json = serde_json::to_value(&outline).unwrap();
+        assert_eq!(json["config"]["exclude"], serde_json::json!(["License"]));
+        let overview = &json["headings"][0];
+        assert_eq!(overview["level"], 2);
+        assert_eq!(overview["text"], "1. Overview");
+        assert_eq!(overview["anchor"], "1-overview");
+        assert_eq!(overview["line"], 6);
+        assert_eq!(overview["children"][1]["anchor"], "12-non-goals");
+        assert_eq!(overview["children"][1]["line"], 10);
+        assert!(overview.get("offset").is_none());
+        assert_eq!(json["diagnostics"], serde_json::json!([]));
+
+        assert!(make_outline("tests/files/does-not-exist.md", Config::default()).is_err());
+    }
+
+    fn generate_and_add(content: String) -> String {
+        let config = parse_json_config_or_use_provided(&content, Config::default()).unwrap();
+        let toc = generate_toc(content.clone(), config);
+        add_toc(content, toc)
+    }
+
+    #[test]
+    fn test_renumber_headings() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            config: Config,
+            expected: &'a str,
+        }
+
+        let tests = [
+            TestCase {
+                name: "Inserts numbers",
+                input: "# Guide\n## Install\n### Cargo\n## Usage",
+                config: Config::default(),
+                expected: "# 1. Guide\n## 1.1. Install\n### 1.1.1. Cargo\n## 1.2. Usage",
+            },
+            TestCase {
+                name: "Replaces stale numbers",
+                input: "## 2.3. Install\n## 2.4. Usage\n## 10. Tips",
+                config: Config::default(),
+                expected: "## 1. Install\n## 2. Usage\n## 3. Tips",
+            },
+            TestCase {
+                name: "Keeps years, numbers and versions",
+                input: "## 2024 Roadmap\n## 404 Errors\n## 2.0 Release",
+                config: Config::default(),
+                expected: "## 1. 2024 Roadmap\n## 2. 404 Errors\n## 3. 2.0 Release",
+            },
+            TestCase {
+                name: "Keeps acronyms with letters",
+                input: "# API Reference\n## A.7 Overview\n# B Usage",
+                config: Config {
+                    numbering: Some(Numbering::Alpha),
+                    ..Default::default()
+                },
+                expected: "# A API Reference\n## A.1 Overview\n# B Usage",
+            },
+            TestCase {
+                name: "Keeps acronyms with roman numerals",
+                input: "## CLI Usage\n## MIX Commands\n### I.4 Install\n## IV Tools",
+                config: Config {
+                    numbering: Some(Numbering::Roman),
+                    ..Default::default()
+                },
+                expected: "## I CLI Usage\n## II MIX Commands\n### II.1 Install\n## III Tools",
+            },
+            TestCase {
+                name: "Replaces numbers with a bare top level and of moved headings",
+                input: "## 3 Roadmap\n### 1.5 Goals\n## 1.2 Config",
+                config: Config {
+                    numbering: Some(Numbering::Decimal),
+                    ..Default::default()
+                },
+                expected: "## 1 Roadmap\n### 1.1 Goals\n## 2 Config",
+            },
+            TestCase {
+                name: "Keeps numbers in other formats",
+                input: "## B.1 Install\n### 1.1. Cargo",
+                config: Config {
+                    numbering: Some(Numbering::Roman),
+                    ..Default::default()
+                },
+                expected: "## I B.1 Install\n### I.1 1.1. Cargo",
+            },
+            TestCase {
+                name: "Removes numbers of top level headings when restarting",
+                input: "# 1 Part\n## Install\n# 2 Appendix\n## 5 License",
+                config: Config {
+                    numbering: Some(Numbering::Decimal),
+                    restart_numbering: true,
+                    ..Default::default()
+                },
+                expected: "# Part\n## 1 Install\n# Appendix\n## 1 License",
+            },
+            TestCase {
+                name: "Leaves headings outside of the ToC alone",
+                input: "# 1984\n<!-- mktoc-ignore -->\n## 1. Ignored\n## Install ##\n```\n## Code\n```\n<h2>HTML</h2>",
+                config: Config {
+                    min_depth: 2,
+                    ..Default::default()
+                },
+                expected: "# 1984\n<!-- mktoc-ignore -->\n## 1. Ignored\n## 1. Install ##\n```\n## Code\n```\n<h2>HTML</h2>",
+            },
+            TestCase {
+                name: "Leaves out missing parents with legacy indent",
+                input: "### Intro\n## Guide\n#### Install",
+                config: Config {
+                    legacy_indent: true,
+                    ..Default::default()
+                },
+                expected: "### 1. Intro\n## 1. Guide\n#### 1.1. Install",
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let content = renumber_headings(test.input.to_string(), test.config.clone());
+            assert_eq!(content, test.expected);
+            assert_eq!(renumber_headings(content.clone(), test.config), content);
+        }
+    }
 }
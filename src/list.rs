@@ -0,0 +1,406 @@
+//! Options for the Markdown list the ToC is rendered as.
+//!
+//! Renderers differ in how far nested lists have to be indented: GitHub is fine
+//! with two spaces while Bitbucket and Python-Markdown need four, so the bullet,
+//! the indentation and the numbering of entries are configurable in the
+//! [`Config`](crate::Config).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The marker of the entries of an unordered list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Bullet {
+    #[default]
+    #[serde(rename = "-")]
+    Dash,
+    #[serde(rename = "*")]
+    Asterisk,
+    #[serde(rename = "+")]
+    Plus,
+}
+
+impl Bullet {
+    pub const ALL: [Bullet; 3] = [Bullet::Dash, Bullet::Asterisk, Bullet::Plus];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Bullet::Dash => "-",
+            Bullet::Asterisk => "*",
+            Bullet::Plus => "+",
+        }
+    }
+}
+
+impl fmt::Display for Bullet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Bullet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Bullet::ALL
+            .into_iter()
+            .find(|bullet| bullet.as_str() == s.trim())
+            .ok_or_else(|| format!("unknown bullet '{}', expected one of: -, *, +", s))
+    }
+}
+
+/// Format of the hierarchical numbers the ToC entries can be prefixed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Numbering {
+    /// `1.`, `1.2.`
+    #[serde(rename = "1.2.")]
+    DecimalDot,
+    /// `1`, `1.2`
+    #[serde(rename = "1.2")]
+    Decimal,
+    /// `A`, `A.1`
+    #[serde(rename = "A.1")]
+    Alpha,
+    /// `I`, `I.1`
+    #[serde(rename = "I.1")]
+    Roman,
+}
+
+impl Numbering {
+    pub const ALL: [Numbering; 4] = [
+        Numbering::DecimalDot,
+        Numbering::Decimal,
+        Numbering::Alpha,
+        Numbering::Roman,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Numbering::DecimalDot => "1.2.",
+            Numbering::Decimal => "1.2",
+            Numbering::Alpha => "A.1",
+            Numbering::Roman => "I.1",
+        }
+    }
+
+    /// formats the numbers of an entry and its parents, starting at the top level
+    pub fn format(&self, numbers: &[usize]) -> String {
+        let mut parts: Vec<String> = numbers.iter().map(usize::to_string).collect();
+        if let Some((&top, first)) = numbers.first().zip(parts.first_mut()) {
+            match self {
+                Numbering::Alpha => *first = alpha(top),
+                Numbering::Roman => *first = roman(top),
+                _ => {}
+            }
+        }
+
+        let number = parts.join(".");
+        match self {
+            Numbering::DecimalDot => format!("{}.", number),
+            _ => number,
+        }
+    }
+
+    /// returns a regex pattern matching numbers in this format with any number of
+    /// parts. The top level only matches the letters A to Z and the roman numerals up
+    /// to XXXIX, so acronyms like `API` or `CLI` aren't taken as numbers.
+    pub(crate) fn pattern(&self) -> String {
+        let top = match self {
+            Numbering::DecimalDot | Numbering::Decimal => String::from("[0-9]+"),
+            Numbering::Alpha => String::from("[A-Z]"),
+            Numbering::Roman => {
+                // longer numerals first, so `III` isn't matched as `I`
+                let mut numerals: Vec<String> = (1..40).map(roman).collect();
+                numerals.sort_by_key(|numeral| std::cmp::Reverse(numeral.len()));
+                format!("(?:{})", numerals.join("|"))
+            }
+        };
+        let trailing_dot = match self {
+            Numbering::DecimalDot => r"\.",
+            _ => "",
+        };
+
+        format!(r"{}(?:\.[0-9]+)*{}", top, trailing_dot)
+    }
+}
+
+impl fmt::Display for Numbering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Numbering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Numbering::ALL
+            .into_iter()
+            .find(|numbering| numbering.name() == s.trim())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Numbering::ALL.iter().map(Numbering::name).collect();
+                format!(
+                    "unknown numbering '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// converts a number to letters like spreadsheet columns: A to Z, AA, AB and so on
+fn alpha(mut number: usize) -> String {
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push((b'A' + (number % 26) as u8) as char);
+        number /= 26;
+    }
+
+    letters.iter().rev().collect()
+}
+
+/// converts a number to upper case roman numerals, numbers above 3999 stay decimal
+fn roman(mut number: usize) -> String {
+    if number == 0 || number > 3999 {
+        return number.to_string();
+    }
+
+    #[rustfmt::skip]
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut numeral = String::new();
+    for (value, letters) in NUMERALS {
+        while number >= value {
+            numeral.push_str(letters);
+            number -= value;
+        }
+    }
+
+    numeral
+}
+
+/// One level of indentation of nested entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "IndentValue", into = "IndentValue")]
+pub enum Indent {
+    /// the given number of spaces, from 1 to 8
+    Spaces(usize),
+    Tab,
+}
+
+impl Indent {
+    /// returns the indentation for the given nesting depth
+    pub fn repeat(&self, depth: usize) -> String {
+        match self {
+            Indent::Spaces(spaces) => " ".repeat(spaces * depth),
+            Indent::Tab => "\t".repeat(depth),
+        }
+    }
+}
+
+/// returns the indentation of an entry if none is configured, from the numbers of the
+/// entry and its parents. Nested entries of ordered lists have to start after the
+/// marker of their parent, which is one space wider from `10.` on, so they are indented
+/// by the width of the parent markers. Unordered lists are indented by two spaces.
+pub(crate) fn default_indent(numbers: &[usize], depth: usize, ordered: bool) -> String {
+    if !ordered {
+        return Indent::Spaces(2).repeat(depth);
+    }
+
+    numbers[..depth]
+        .iter()
+        .map(|number| " ".repeat(number.to_string().len() + 2))
+        .collect()
+}
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indent::Spaces(spaces) => write!(f, "{}", spaces),
+            Indent::Tab => f.write_str("tab"),
+        }
+    }
+}
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        if value.eq_ignore_ascii_case("tab") {
+            return Ok(Indent::Tab);
+        }
+
+        match value.parse() {
+            Ok(spaces) => IndentValue::Spaces(spaces).try_into(),
+            Err(_) => Err(format!(
+                "invalid indent '{}', expected a number of spaces or 'tab'",
+                s
+            )),
+        }
+    }
+}
+
+/// How the indentation is written in the inline config: `4` or `"tab"`
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum IndentValue {
+    Spaces(usize),
+    Name(String),
+}
+
+impl TryFrom<IndentValue> for Indent {
+    type Error = String;
+
+    fn try_from(value: IndentValue) -> Result<Self, Self::Error> {
+        match value {
+            IndentValue::Spaces(spaces @ 1..=8) => Ok(Indent::Spaces(spaces)),
+            IndentValue::Spaces(spaces) => Err(format!(
+                "indent of {} spaces out of bound, expected 1 to 8",
+                spaces
+            )),
+            IndentValue::Name(name) => name.parse(),
+        }
+    }
+}
+
+impl From<Indent> for IndentValue {
+    fn from(indent: Indent) -> Self {
+        match indent {
+            Indent::Spaces(spaces) => IndentValue::Spaces(spaces),
+            Indent::Tab => IndentValue::Name(String::from("tab")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bullet_from_str() {
+        assert_eq!("*".parse::<Bullet>(), Ok(Bullet::Asterisk));
+        assert_eq!(" + ".parse::<Bullet>(), Ok(Bullet::Plus));
+        assert!("#".parse::<Bullet>().is_err());
+        assert_eq!(
+            serde_json::from_str::<Bullet>(r#""-""#).unwrap(),
+            Bullet::Dash
+        );
+    }
+
+    #[test]
+    fn test_numbering() {
+        struct TestCase<'a> {
+            numbering: Numbering,
+            numbers: &'a [usize],
+            expected: &'a str,
+        }
+        let test_cases = [
+            TestCase {
+                numbering: Numbering::DecimalDot,
+                numbers: &[1],
+                expected: "1.",
+            },
+            TestCase {
+                numbering: Numbering::DecimalDot,
+                numbers: &[1, 2, 3],
+                expected: "1.2.3.",
+            },
+            TestCase {
+                numbering: Numbering::Decimal,
+                numbers: &[10, 2],
+                expected: "10.2",
+            },
+            TestCase {
+                numbering: Numbering::Alpha,
+                numbers: &[2, 1],
+                expected: "B.1",
+            },
+            TestCase {
+                numbering: Numbering::Alpha,
+                numbers: &[28],
+                expected: "AB",
+            },
+            TestCase {
+                numbering: Numbering::Roman,
+                numbers: &[4, 9],
+                expected: "IV.9",
+            },
+            TestCase {
+                numbering: Numbering::Roman,
+                numbers: &[1994],
+                expected: "MCMXCIV",
+            },
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                test_case.numbering.format(test_case.numbers),
+                test_case.expected
+            );
+        }
+
+        assert_eq!("A.1".parse::<Numbering>(), Ok(Numbering::Alpha));
+        assert!("a.1".parse::<Numbering>().is_err());
+        assert_eq!(
+            serde_json::from_str::<Numbering>(r#""1.2.""#).unwrap(),
+            Numbering::DecimalDot
+        );
+    }
+
+    #[test]
+    fn test_default_indent() {
+        assert_eq!(default_indent(&[1, 2], 1, false), "  ");
+        assert_eq!(default_indent(&[1, 2, 1], 2, true), "      ");
+        assert_eq!(default_indent(&[10, 1], 1, true), "    ");
+        assert_eq!(default_indent(&[9, 100, 1], 2, true), "        ");
+    }
+
+    #[test]
+    fn test_indent() {
+        struct TestCase<'a> {
+            input: &'a str,
+            expected: Result<Indent, ()>,
+        }
+        let test_cases = [
+            TestCase {
+                input: "4",
+                expected: Ok(Indent::Spaces(4)),
+            },
+            TestCase {
+                input: "\"tab\"",
+                expected: Ok(Indent::Tab),
+            },
+            TestCase {
+                input: "\"3\"",
+                expected: Ok(Indent::Spaces(3)),
+            },
+            TestCase {
+                input: "0",
+                expected: Err(()),
+            },
+            TestCase {
+                input: "9",
+                expected: Err(()),
+            },
+            TestCase {
+                input: "\"tabs\"",
+                expected: Err(()),
+            },
+        ];
+
+        for test_case in test_cases {
+            let indent = serde_json::from_str::<Indent>(test_case.input).map_err(|_| ());
+            assert_eq!(indent, test_case.expected, "{}", test_case.input);
+        }
+
+        assert_eq!(Indent::Spaces(4).repeat(2), "        ");
+        assert_eq!(Indent::Tab.repeat(2), "\t\t");
+        assert_eq!("TAB".parse::<Indent>(), Ok(Indent::Tab));
+        assert_eq!(Indent::Tab.to_string(), "tab");
+    }
+}
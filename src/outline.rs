@@ -0,0 +1,336 @@
+//! The outline of a document: its headings and how they nest in the ToC.
+//!
+//! [`parse_headings`] returns the headings that are part of the ToC and [`TocTree`]
+//! nests them the way the ToC does. [`generate_toc`](crate::generate_toc) renders
+//! this tree, so tools working with the outline get exactly the entries of the ToC.
+
+use crate::{
+    Config, Diagnostic, Numbering, inline, nesting_depth, number_regex, parser, unique_anchor,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// A heading that is part of the ToC
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Heading {
+    /// heading level from 1 to 6
+    pub level: i32,
+    /// inline Markdown of the heading without indentation, closing sequence and attributes
+    pub raw_text: String,
+    /// text of the ToC entry: the label of the heading or its text, without links,
+    /// images and HTML. Inline code and emphasis are kept unless `strip_formatting` is set.
+    pub text: String,
+    /// the text as it is rendered, without any Markdown
+    pub plain_text: String,
+    /// anchor of the heading without `#`
+    pub anchor: String,
+    /// number appended to the anchor of a duplicate heading, 0 for the first one
+    pub duplicate_index: usize,
+    /// line the heading starts at, counted from 1
+    pub line: usize,
+    /// lines of the heading, counted from 1
+    pub lines: Range<usize>,
+    /// byte range of the lines of the heading in the document, HTML headings span
+    /// their whole HTML block
+    pub bytes: Range<usize>,
+    /// byte offset of the text of ATX headings, only those can be renumbered
+    #[serde(skip)]
+    pub(crate) offset: Option<usize>,
+}
+
+/// An entry of the ToC with the entries nested below it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TocNode {
+    #[serde(flatten)]
+    pub heading: Heading,
+    /// nesting depth in the ToC, 0 at the root. Only with `legacy_indent` a child can
+    /// be more than one level deeper than its parent.
+    pub depth: usize,
+    /// numbers of the entry and its parents within their lists, e.g. `[1, 2]` for
+    /// the second entry below the first entry at the root. With `legacy_indent` a
+    /// missing parent is 0, it is left out of the formatted number.
+    pub numbers: Vec<usize>,
+    pub children: Vec<TocNode>,
+}
+
+/// The headings of a document nested the way they are shown in the ToC
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct TocTree {
+    /// entries at the root of the ToC
+    pub nodes: Vec<TocNode>,
+}
+
+/// The outline of a file together with the config it was created with, see
+/// [`make_outline`](crate::make_outline)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Outline {
+    /// the inline config of the file or the provided one
+    pub config: Config,
+    pub headings: TocTree,
+    /// warnings about the file and its config, see
+    /// [`make_toc_with_diagnostics`](crate::make_toc_with_diagnostics)
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl TocTree {
+    /// nests the headings by their level. The shallowest level is at the root and
+    /// every heading is a child of the closest previous heading with a lower level,
+    /// or by the absolute level with `legacy_indent`.
+    ///
+    /// ```
+    /// use mktoc::*;
+    /// let config = Config::default();
+    /// let tree = TocTree::new(parse_headings("## Install\n### Cargo\n## Usage", &config), &config);
+    /// assert_eq!(tree.nodes.len(), 2);
+    /// assert_eq!(tree.nodes[0].children[0].heading.anchor, "cargo");
+    /// assert_eq!(tree.nodes[0].children[0].numbers, [1, 1]);
+    /// ```
+    pub fn new(headings: Vec<Heading>, config: &Config) -> Self {
+        let mut tree = TocTree::default();
+        // levels of the entries the next entry can be nested in
+        let mut parent_levels: Vec<i32> = Vec::new();
+        // numbers of the last entries of each depth, for ordered lists and numbering
+        let mut counters: Vec<usize> = Vec::new();
+
+        for heading in headings {
+            let depth = if config.legacy_indent {
+                // levels 1 and 2 are both at the root
+                (heading.level - 2).max(0) as usize
+            } else {
+                nesting_depth(&mut parent_levels, heading.level)
+            };
+
+            // every nested list is numbered from 1
+            counters.resize(depth + 1, 0);
+            counters[depth] += 1;
+
+            let mut siblings = &mut tree.nodes;
+            while siblings.last().is_some_and(|last| last.depth < depth) {
+                siblings = &mut siblings.last_mut().unwrap().children;
+            }
+            siblings.push(TocNode {
+                heading,
+                depth,
+                numbers: counters.clone(),
+                children: Vec::new(),
+            });
+        }
+
+        tree
+    }
+
+    /// returns all entries in document order
+    pub fn iter(&self) -> impl Iterator<Item = &TocNode> {
+        let mut nodes = Vec::new();
+        let mut stack: Vec<&TocNode> = self.nodes.iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+
+        nodes.into_iter()
+    }
+}
+
+/// scans the content and returns the headings that are part of the ToC in document
+/// order: headings within `min_depth` and `max_depth` that are neither ignored nor
+/// excluded, see [`generate_toc`](crate::generate_toc).
+///
+/// Anchors are created for all headings, so the anchors of duplicate headings are
+/// the same as in the rendered document.
+///
+/// ```
+/// use mktoc::*;
+/// let headings = parse_headings("# Hello `World`\n\n# Hello World", &Config::default());
+/// assert_eq!(headings[0].text, "Hello `World`");
+/// assert_eq!(headings[1].anchor, "hello-world-1");
+/// assert_eq!(headings[1].duplicate_index, 1);
+/// assert_eq!(headings[1].lines, 3..4);
+/// ```
+pub fn parse_headings(content: &str, config: &Config) -> Vec<Heading> {
+    let mut found_headings: HashMap<String, i64> = HashMap::new();
+    let mut headings = Vec::new();
+    let exclude_patterns = config.exclude_patterns();
+    let re_number = config
+        .renumber_headings
+        .then(|| number_regex(config.numbering.unwrap_or(Numbering::DecimalDot)));
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line = |offset: usize| line_starts.partition_point(|&start| start <= offset);
+    let document = parser::scan(content);
+
+    // explicit anchors are taken, generated ones get a number appended instead
+    for id in document
+        .headings
+        .iter()
+        .filter_map(|heading| heading.id.as_ref())
+    {
+        found_headings.entry(id.clone()).or_insert(0);
+    }
+
+    for heading in document.headings {
+        let level = heading.level;
+        let inline = inline::render(&heading.text, &document.link_definitions);
+        // anchors are created for all headings, even the ones not in the ToC,
+        // because they take up anchors in the rendered document as well
+        let (anchor, duplicate_index) = match &heading.id {
+            // explicit anchors are used as they are
+            Some(id) => (id.clone(), 0),
+            None => unique_anchor(
+                &mut found_headings,
+                config.slug_style.slug(&inline.plain),
+                config.slug_style,
+            ),
+        };
+
+        if level < config.min_depth {
+            continue;
+        }
+
+        if level > config.max_depth {
+            continue;
+        }
+
+        let plain_text = inline
+            .plain
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        // numbers from previous runs are not part of the text to exclude
+        let exclude_text = match &re_number {
+            Some(re_number) => re_number.replace(&plain_text, ""),
+            None => plain_text.as_str().into(),
+        };
+        if heading.ignored || exclude_patterns.iter().any(|re| re.is_match(&exclude_text)) {
+            continue;
+        }
+
+        // a label replaces the heading text in the ToC, the anchor stays the same
+        let label = match &heading.label {
+            Some(label) => inline::render(label, &document.link_definitions),
+            None => inline,
+        };
+        let text = if config.strip_formatting {
+            label.plain.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            label.display
+        };
+
+        headings.push(Heading {
+            level,
+            raw_text: heading.text,
+            text,
+            plain_text,
+            anchor,
+            duplicate_index,
+            line: line(heading.span.start),
+            lines: line(heading.span.start)..line(heading.span.end) + 1,
+            bytes: heading.span,
+            offset: heading.offset,
+        });
+    }
+
+    headings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_headings() {
+        let content = "---\ntitle: Test\n---\n# Guide\n\nUsing *foo*\n===\n\n<!-- mktoc-ignore -->\n## Ignored\n\n<h2 id=\"install\">Install</h2>\n\n## Guide {data-toc-label=\"More\"}\n";
+        let headings = parse_headings(content, &Config::default());
+
+        let expected = [
+            (1, "Guide", "Guide", "guide", 0, 4..5),
+            (1, "Using *foo*", "Using *foo*", "using-foo", 0, 6..8),
+            (2, "Install", "Install", "install", 0, 12..13),
+            (2, "Guide", "More", "guide-1", 1, 14..15),
+        ];
+        assert_eq!(headings.len(), expected.len());
+        for (heading, (level, raw_text, text, anchor, duplicate_index, lines)) in
+            headings.iter().zip(expected)
+        {
+            assert_eq!(heading.level, level);
+            assert_eq!(heading.raw_text, raw_text);
+            assert_eq!(heading.text, text);
+            assert_eq!(heading.anchor, anchor);
+            assert_eq!(heading.duplicate_index, duplicate_index);
+            assert_eq!(heading.lines, lines);
+        }
+        assert_eq!(headings[1].plain_text, "Using foo");
+        assert_eq!(&content[headings[1].bytes.clone()], "Using *foo*\n===");
+    }
+
+    #[test]
+    fn test_toc_tree() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            legacy_indent: bool,
+            /// anchor, depth and number of the children of each node in document order
+            expected: Vec<(&'a str, usize, Vec<usize>, usize)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "nested by level",
+                input: "## A\n### B\n#### C\n### D\n## E",
+                legacy_indent: false,
+                expected: vec![
+                    ("a", 0, vec![1], 2),
+                    ("b", 1, vec![1, 1], 1),
+                    ("c", 2, vec![1, 1, 1], 0),
+                    ("d", 1, vec![1, 2], 0),
+                    ("e", 0, vec![2], 0),
+                ],
+            },
+            TestCase {
+                name: "skipped levels",
+                input: "# A\n### B\n## C",
+                legacy_indent: false,
+                expected: vec![
+                    ("a", 0, vec![1], 2),
+                    ("b", 1, vec![1, 1], 0),
+                    ("c", 1, vec![1, 2], 0),
+                ],
+            },
+            TestCase {
+                name: "legacy indent",
+                input: "### A\n# B\n## C\n#### D",
+                legacy_indent: true,
+                expected: vec![
+                    ("a", 1, vec![0, 1], 0),
+                    ("b", 0, vec![1], 0),
+                    ("c", 0, vec![2], 1),
+                    ("d", 2, vec![2, 0, 1], 0),
+                ],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let config = Config {
+                legacy_indent: test.legacy_indent,
+                ..Default::default()
+            };
+            let tree = TocTree::new(parse_headings(test.input, &config), &config);
+            let nodes: Vec<(&str, usize, Vec<usize>, usize)> = tree
+                .iter()
+                .map(|node| {
+                    (
+                        node.heading.anchor.as_str(),
+                        node.depth,
+                        node.numbers.clone(),
+                        node.children.len(),
+                    )
+                })
+                .collect();
+            assert_eq!(nodes, test.expected);
+        }
+    }
+}
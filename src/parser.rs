@@ -0,0 +1,1774 @@
+//! Block scanner used to find the headings of a Markdown document.
+//!
+//! The scanner follows the block structure rules of the CommonMark spec
+//! (<https://spec.commonmark.org/>) closely enough to decide which lines
+//! render as headings. It does not build a full document tree, it only keeps
+//! track of the open containers (block quotes and list items) and the current leaf block
+//! (paragraph, fenced code or HTML block) for every line.
+
+use crate::inline;
+use regex::Regex;
+use std::collections::HashSet;
+use std::ops::Range;
+
+const IGNORE: &str = "<!-- mktoc-ignore -->";
+const IGNORE_START: &str = "<!-- mktoc-ignore-start -->";
+const IGNORE_END: &str = "<!-- mktoc-ignore-end -->";
+
+/// A heading found by the block scanner
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RawHeading {
+    /// heading level from 1 to 6
+    pub(crate) level: i32,
+    /// raw inline content, without indentation and closing sequence
+    pub(crate) text: String,
+    /// explicit anchor, like the `id` attribute of an HTML heading
+    pub(crate) id: Option<String>,
+    /// set for headings after a `<!-- mktoc-ignore -->` marker or inside an ignored region
+    pub(crate) ignored: bool,
+    /// text used in the ToC instead of the heading text, from a
+    /// `<!-- mktoc: label="Setup" -->` comment or a `data-toc-label` attribute
+    pub(crate) label: Option<String>,
+    /// byte offset of the text of ATX headings in the document. Only those can be
+    /// renumbered, a Setext heading starting with `1. ` would become a list item.
+    pub(crate) offset: Option<usize>,
+    /// byte range of the lines of the heading in the document without the final line
+    /// break, HTML headings span the whole HTML block
+    pub(crate) span: Range<usize>,
+}
+
+/// The attributes of a heading mktoc cares about
+#[derive(Debug, Default, PartialEq)]
+struct Attributes {
+    id: Option<String>,
+    label: Option<String>,
+}
+
+/// The parts of a document the block scanner keeps track of
+#[derive(Debug, Default)]
+pub(crate) struct Document {
+    /// all headings in document order
+    pub(crate) headings: Vec<RawHeading>,
+    /// byte ranges of the lines belonging to HTML blocks, indentation and
+    /// container markers are not included
+    pub(crate) html_lines: Vec<Range<usize>>,
+    /// normalized labels of all link reference definitions like `[label]: /url`
+    pub(crate) link_definitions: HashSet<String>,
+}
+
+/// Block-level elements that can contain other blocks
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    BlockQuote,
+    /// `indent` is the column the content of the item starts at, `empty` is
+    /// set while an item that started with a blank line has no content yet
+    ListItem {
+        indent: usize,
+        empty: bool,
+    },
+}
+
+/// The leaf block the previous line belonged to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Leaf {
+    None,
+    Paragraph,
+    FencedCode { fence: char, len: usize },
+    Html(HtmlEnd),
+}
+
+/// End condition of an HTML block, see the seven kinds of HTML blocks in the spec
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HtmlEnd {
+    /// `<script>`, `<pre>`, `<style>` and `<textarea>` end at their closing tag
+    RawText,
+    /// `<!-- ... -->`
+    Comment,
+    /// `<? ... ?>`
+    ProcessingInstruction,
+    /// `<!DOCTYPE ...>`
+    Declaration,
+    /// `<![CDATA[ ... ]]>`
+    CData,
+    /// all other HTML blocks end at the next blank line
+    BlankLine,
+}
+
+impl HtmlEnd {
+    /// checks if the given line ends the HTML block
+    fn is_end(self, line: &str) -> bool {
+        match self {
+            HtmlEnd::RawText => {
+                let lower = line.to_ascii_lowercase();
+                ["</script>", "</pre>", "</style>", "</textarea>"]
+                    .iter()
+                    .any(|tag| lower.contains(tag))
+            }
+            HtmlEnd::Comment => line.contains("-->"),
+            HtmlEnd::ProcessingInstruction => line.contains("?>"),
+            HtmlEnd::Declaration => line.contains('>'),
+            HtmlEnd::CData => line.contains("]]>"),
+            HtmlEnd::BlankLine => is_blank(line),
+        }
+    }
+}
+
+/// Tag names that start an HTML block which ends at a blank line
+#[rustfmt::skip]
+const BLOCK_TAGS: &[&str] = &[
+    "address", "article", "aside", "base", "basefont", "blockquote", "body", "caption", "center",
+    "col", "colgroup", "dd", "details", "dialog", "dir", "div", "dl", "dt", "fieldset",
+    "figcaption", "figure", "footer", "form", "frame", "frameset", "h1", "h2", "h3", "h4", "h5",
+    "h6", "head", "header", "hr", "html", "iframe", "legend", "li", "link", "main", "menu",
+    "menuitem", "nav", "noframes", "ol", "optgroup", "option", "p", "param", "search", "section",
+    "summary", "table", "tbody", "td", "tfoot", "th", "thead", "title", "tr", "track", "ul",
+];
+
+/// A single line of input. Container markers and indentation are consumed
+/// column by column, tabs are expanded to a tab stop of 4 as the spec requires.
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    text: &'a str,
+    /// byte offset of the next unconsumed character
+    pos: usize,
+    /// column of `pos`
+    col: usize,
+    /// columns left over from a partially consumed tab
+    partial: usize,
+}
+
+impl<'a> Line<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            pos: 0,
+            col: 0,
+            partial: 0,
+        }
+    }
+
+    /// number of whitespace columns in front of the next non-whitespace character
+    fn indent(&self) -> usize {
+        let mut col = self.col;
+        for c in self.text[self.pos..].chars() {
+            match c {
+                ' ' => col += 1,
+                '\t' => col += 4 - col % 4,
+                _ => break,
+            }
+        }
+
+        self.partial + col - self.col
+    }
+
+    fn is_blank(&self) -> bool {
+        is_blank(&self.text[self.pos..])
+    }
+
+    /// consumes `n` columns of whitespace, splitting tabs if needed
+    fn skip_cols(&mut self, mut n: usize) {
+        let taken = n.min(self.partial);
+        self.partial -= taken;
+        n -= taken;
+
+        while n > 0 {
+            match self.text.as_bytes().get(self.pos) {
+                Some(b' ') => {
+                    self.pos += 1;
+                    self.col += 1;
+                    n -= 1;
+                }
+                Some(b'\t') => {
+                    let width = 4 - self.col % 4;
+                    self.pos += 1;
+                    self.col += width;
+                    if width > n {
+                        self.partial = width - n;
+                        n = 0;
+                    } else {
+                        n -= width;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// consumes all whitespace in front of the next non-whitespace character
+    fn skip_indent(&mut self) {
+        let indent = self.indent();
+        self.skip_cols(indent);
+        self.partial = 0;
+    }
+
+    /// the unconsumed rest of the line. Only meaningful after `skip_indent`.
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    /// the column of the next unconsumed character
+    fn column(&self) -> usize {
+        self.col - self.partial
+    }
+
+    /// consumes a list item marker (`-`, `+`, `*`, `1.` or `1)`) and the
+    /// following spaces if present. Returns the new list item container.
+    fn consume_list_marker(&mut self, in_paragraph: bool) -> Option<Container> {
+        if self.indent() >= 4 {
+            return None;
+        }
+
+        let start = self.column();
+        let mut line = *self;
+        line.skip_indent();
+        if is_thematic_break(line.rest()) {
+            return None;
+        }
+
+        let marker_len = list_marker(line.rest(), in_paragraph)?;
+        let after_marker = &line.rest()[marker_len..];
+        if !after_marker.is_empty() && !after_marker.starts_with([' ', '\t']) {
+            return None;
+        }
+        line.pos += marker_len;
+        line.col += marker_len;
+
+        let marker_width = line.column() - start;
+        let spaces = line.indent();
+        let container = if line.is_blank() {
+            // an empty list item can't interrupt a paragraph
+            if in_paragraph {
+                return None;
+            }
+            line.skip_indent();
+            Container::ListItem {
+                indent: marker_width + 1,
+                empty: true,
+            }
+        } else if spaces > 4 {
+            // the content starts with an indented code block
+            line.skip_cols(1);
+            Container::ListItem {
+                indent: marker_width + 1,
+                empty: false,
+            }
+        } else {
+            line.skip_cols(spaces);
+            Container::ListItem {
+                indent: marker_width + spaces,
+                empty: false,
+            }
+        };
+        *self = line;
+
+        Some(container)
+    }
+
+    /// consumes a block quote marker (`>` and one optional space) if present
+    fn consume_blockquote_marker(&mut self) -> bool {
+        if self.indent() >= 4 {
+            return false;
+        }
+
+        let mut line = *self;
+        line.skip_indent();
+        if !line.rest().starts_with('>') {
+            return false;
+        }
+
+        line.pos += 1;
+        line.col += 1;
+        if line.rest().starts_with([' ', '\t']) {
+            line.skip_cols(1);
+        }
+        *self = line;
+
+        true
+    }
+}
+
+/// Scans the document line by line and collects all headings
+#[derive(Debug)]
+struct Scanner {
+    containers: Vec<Container>,
+    leaf: Leaf,
+    /// text of the open paragraph, it becomes a heading when followed by a
+    /// Setext underline
+    paragraph: Vec<String>,
+    /// byte offsets of the lines of the open paragraph
+    paragraph_starts: Vec<usize>,
+    /// number of lines at the start of the paragraph that are link reference definitions
+    definitions: usize,
+    /// text of the open HTML block, searched for heading elements once the block ends
+    html: String,
+    /// byte range of the open HTML block
+    html_span: Range<usize>,
+    /// the previous line was a `<!-- mktoc-ignore -->` marker
+    ignore_next: bool,
+    /// label from a `<!-- mktoc: label="…" -->` comment on the previous line
+    next_label: Option<String>,
+    /// the current line is between `<!-- mktoc-ignore-start -->` and `<!-- mktoc-ignore-end -->`
+    ignore_region: bool,
+    /// whether the headings of the open paragraph or HTML block are ignored
+    leaf_ignored: bool,
+    /// label for the first heading of the open paragraph or HTML block
+    leaf_label: Option<String>,
+    document: Document,
+}
+
+impl Scanner {
+    fn new() -> Self {
+        Self {
+            containers: Vec::new(),
+            leaf: Leaf::None,
+            paragraph: Vec::new(),
+            paragraph_starts: Vec::new(),
+            definitions: 0,
+            html: String::new(),
+            html_span: 0..0,
+            ignore_next: false,
+            next_label: None,
+            ignore_region: false,
+            leaf_ignored: false,
+            leaf_label: None,
+            document: Document::default(),
+        }
+    }
+
+    /// processes the next line, `offset` is the byte offset of the line in the document
+    fn feed(&mut self, text: &str, offset: usize) {
+        let mut line = Line::new(text);
+        let ignore = std::mem::take(&mut self.ignore_next) || self.ignore_region;
+        let label = self.next_label.take();
+
+        // 1. check which of the open containers are continued by this line
+        let mut matched = 0;
+        for container in &mut self.containers {
+            let continued = match container {
+                Container::BlockQuote => line.consume_blockquote_marker(),
+                Container::ListItem { indent, empty } => {
+                    if line.is_blank() {
+                        // an item can start with at most one blank line
+                        !*empty
+                    } else if line.indent() >= *indent {
+                        line.skip_cols(*indent);
+                        *empty = false;
+                        true
+                    } else {
+                        false
+                    }
+                }
+            };
+            if !continued {
+                break;
+            }
+            matched += 1;
+        }
+
+        if matched < self.containers.len() {
+            // a paragraph can continue lazily without the container markers
+            if self.leaf == Leaf::Paragraph && !interrupts_paragraph(line) {
+                self.continue_paragraph(line, offset);
+                return;
+            }
+            self.containers.truncate(matched);
+            self.close_html();
+            self.leaf = Leaf::None;
+        }
+
+        // 2. inside code and HTML blocks only the end condition matters
+        match self.leaf {
+            Leaf::FencedCode { fence, len } => {
+                if line.indent() < 4 {
+                    line.skip_indent();
+                    if is_closing_fence(line.rest(), fence, len) {
+                        self.leaf = Leaf::None;
+                    }
+                }
+                return;
+            }
+            Leaf::Html(end) => {
+                if end != HtmlEnd::BlankLine || !line.is_blank() {
+                    line.skip_indent();
+                    self.push_html_line(line, offset);
+                }
+                if end.is_end(line.rest()) {
+                    self.close_html();
+                    self.leaf = Leaf::None;
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        // 3. open new containers
+        loop {
+            let container = if line.consume_blockquote_marker() {
+                Container::BlockQuote
+            } else if let Some(item) = line.consume_list_marker(self.leaf == Leaf::Paragraph) {
+                item
+            } else {
+                break;
+            };
+            self.containers.push(container);
+            self.leaf = Leaf::None;
+        }
+
+        // 4. find the leaf block this line belongs to
+        if line.is_blank() {
+            self.leaf = Leaf::None;
+            return;
+        }
+
+        if line.indent() >= 4 {
+            // either a paragraph continuation or an indented code block
+            if self.leaf == Leaf::Paragraph {
+                self.continue_paragraph(line, offset);
+            }
+            return;
+        }
+
+        line.skip_indent();
+        let rest = line.rest();
+
+        // a paragraph of link reference definitions only can't be a heading
+        if self.leaf == Leaf::Paragraph
+            && self.definitions < self.paragraph.len()
+            && let Some(level) = setext_underline(rest)
+        {
+            let content = self.paragraph[self.definitions..].join(" ");
+            let (content, attributes) = split_attributes(&content);
+            self.document.headings.push(RawHeading {
+                level,
+                text: content.to_string(),
+                id: attributes.id,
+                ignored: self.leaf_ignored,
+                label: attributes.label.or(self.leaf_label.take()),
+                offset: None,
+                span: self.paragraph_starts[self.definitions]..offset + text.len(),
+            });
+            self.leaf = Leaf::None;
+        } else if let Some(mut heading) = atx_heading(rest) {
+            heading.offset = heading.offset.map(|start| offset + line.pos + start);
+            heading.span = offset..offset + text.len();
+            heading.ignored = ignore;
+            heading.label = heading.label.or(label);
+            self.document.headings.push(heading);
+            self.leaf = Leaf::None;
+        } else if let Some((fence, len)) = opening_fence(rest) {
+            self.leaf = Leaf::FencedCode { fence, len };
+        } else if let Some(end) = html_block_start(rest, self.leaf == Leaf::Paragraph) {
+            self.leaf_ignored = ignore;
+            self.leaf_label = label;
+            self.push_html_line(line, offset);
+            self.leaf = if end != HtmlEnd::BlankLine && end.is_end(rest) {
+                self.close_html();
+                Leaf::None
+            } else {
+                Leaf::Html(end)
+            };
+        } else if is_thematic_break(rest) {
+            self.leaf = Leaf::None;
+        } else if self.leaf == Leaf::Paragraph {
+            self.continue_paragraph(line, offset);
+        } else {
+            self.paragraph.clear();
+            self.paragraph_starts.clear();
+            self.definitions = 0;
+            self.leaf_ignored = ignore;
+            self.leaf_label = label;
+            self.leaf = Leaf::Paragraph;
+            self.continue_paragraph(line, offset);
+        }
+    }
+
+    fn push_html_line(&mut self, line: Line, offset: usize) {
+        match line.rest().trim_end() {
+            IGNORE => self.ignore_next = true,
+            IGNORE_START => self.ignore_region = true,
+            IGNORE_END => self.ignore_region = false,
+            text => {
+                if let Some(label) = label_comment(text) {
+                    self.next_label = Some(label);
+                }
+            }
+        }
+        if self.html.is_empty() {
+            self.html_span.start = offset;
+        }
+        self.html_span.end = offset + line.text.len();
+        self.html.push_str(line.rest());
+        self.html.push('\n');
+        self.document
+            .html_lines
+            .push(offset + line.pos..offset + line.text.len());
+    }
+
+    /// adds the heading elements of the HTML block that just ended
+    fn close_html(&mut self) {
+        let mut headings = html_headings(&self.html);
+        if let Some(first) = headings.first_mut() {
+            first.label = first.label.take().or(self.leaf_label.take());
+        }
+        for heading in &mut headings {
+            heading.ignored = self.leaf_ignored;
+            heading.span = self.html_span.clone();
+        }
+        self.document.headings.extend(headings);
+        self.html.clear();
+    }
+
+    fn continue_paragraph(&mut self, mut line: Line, offset: usize) {
+        line.skip_indent();
+        // link reference definitions can only appear at the start of a paragraph
+        if self.definitions == self.paragraph.len()
+            && let Some(label) = link_definition(line.rest())
+        {
+            self.document.link_definitions.insert(label);
+            self.definitions += 1;
+        }
+        self.paragraph
+            .push(line.rest().trim_end_matches([' ', '\t']).to_string());
+        self.paragraph_starts.push(offset);
+    }
+}
+
+/// scans the Markdown content and returns its headings and HTML blocks
+pub(crate) fn scan(content: &str) -> Document {
+    let mut scanner = Scanner::new();
+    let mut offset = front_matter_len(content);
+    for line in content[offset..].split_inclusive('\n') {
+        let text = line.strip_suffix('\n').unwrap_or(line);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        scanner.feed(text, offset);
+        offset += line.len();
+    }
+    scanner.close_html();
+
+    scanner.document
+}
+
+/// returns the length in bytes of the front matter at the start of the document.
+///
+/// Static site generators support YAML (`---`), TOML (`+++`) and JSON (`;;;`)
+/// front matter. Its content is not Markdown, a YAML comment like `# draft`
+/// would otherwise be read as a heading and the closing `---` as a Setext underline.
+fn front_matter_len(content: &str) -> usize {
+    let mut lines = content.split_inclusive('\n');
+    let Some(first) = lines.next() else {
+        return 0;
+    };
+
+    let closing: &[&str] = match first.trim_end() {
+        "---" => &["---", "..."],
+        "+++" => &["+++"],
+        ";;;" => &[";;;"],
+        _ => return 0,
+    };
+
+    let mut len = first.len();
+    for line in lines {
+        len += line.len();
+        if closing.contains(&line.trim_end()) {
+            return len;
+        }
+    }
+
+    // without a closing delimiter there is no front matter
+    0
+}
+
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| c == ' ' || c == '\t')
+}
+
+/// parses a link reference definition like `[label]: /url "title"` and returns the
+/// normalized label. A title on the following line is not supported.
+fn link_definition(text: &str) -> Option<String> {
+    let rest = text.strip_prefix('[')?;
+    let end = inline::label_end(rest)?;
+    let label = &rest[..end];
+    let rest = rest[end + 1..].strip_prefix(':')?.trim_start();
+
+    let destination_len = if let Some(bracketed) = rest.strip_prefix('<') {
+        bracketed.find('>')? + 2
+    } else {
+        rest.find(char::is_whitespace).unwrap_or(rest.len())
+    };
+    if destination_len == 0 {
+        return None;
+    }
+
+    // the destination has to be followed by whitespace or the end of the line
+    let after = &rest[destination_len..];
+    if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    Some(inline::normalize_label(label))
+}
+
+/// checks if the line would start a new block instead of continuing a paragraph
+fn interrupts_paragraph(mut line: Line) -> bool {
+    if line.is_blank() {
+        return true;
+    }
+    if line.indent() >= 4 {
+        return false;
+    }
+    if line.consume_list_marker(true).is_some() {
+        return true;
+    }
+
+    line.skip_indent();
+    let rest = line.rest();
+
+    rest.starts_with('>')
+        || atx_heading(rest).is_some()
+        || opening_fence(rest).is_some()
+        || html_block_start(rest, true).is_some()
+        || is_thematic_break(rest)
+}
+
+/// returns the length of the list item marker at the start of the text.
+/// Only bullets and ordered lists starting at 1 can interrupt a paragraph.
+fn list_marker(text: &str, in_paragraph: bool) -> Option<usize> {
+    if text.starts_with(['-', '+', '*']) {
+        return Some(1);
+    }
+
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    if !(1..=9).contains(&digits) || !text[digits..].starts_with(['.', ')']) {
+        return None;
+    }
+    if in_paragraph && text[..digits].parse::<u32>() != Ok(1) {
+        return None;
+    }
+
+    Some(digits + 1)
+}
+
+/// parses an ATX heading (`## Heading ##`) from a line without indentation
+fn atx_heading(text: &str) -> Option<RawHeading> {
+    let level = text.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+
+    let content = &text[level..];
+    if !content.is_empty() && !content.starts_with([' ', '\t']) {
+        return None;
+    }
+    let start = text.len() - content.trim_start_matches([' ', '\t']).len();
+
+    let mut content = content.trim_matches([' ', '\t']);
+    // remove the optional closing sequence of `#` characters
+    let without_closing = content.trim_end_matches('#');
+    if without_closing.is_empty() {
+        content = without_closing;
+    } else if without_closing.ends_with([' ', '\t']) {
+        content = without_closing.trim_end_matches([' ', '\t']);
+    }
+
+    let (content, attributes) = split_attributes(content);
+
+    Some(RawHeading {
+        level: level as i32,
+        text: content.to_string(),
+        id: attributes.id,
+        ignored: false,
+        label: attributes.label,
+        offset: Some(start),
+        span: 0..0,
+    })
+}
+
+/// splits a trailing attribute block like `{#install .class}` off the heading
+/// text and returns the explicit id and `data-toc-label` from it. Blocks containing
+/// anything but ids, classes, `key=value` pairs or `-` are part of the text.
+fn split_attributes(text: &str) -> (&str, Attributes) {
+    let Some((before, block)) = text
+        .strip_suffix('}')
+        .and_then(|text| text.rsplit_once('{'))
+    else {
+        return (text, Attributes::default());
+    };
+    let Some(tokens) = attribute_tokens(block).filter(|tokens| !tokens.is_empty()) else {
+        return (text, Attributes::default());
+    };
+
+    let mut attributes = Attributes::default();
+    for token in tokens {
+        if let Some(value) = token.strip_prefix('#') {
+            if value.is_empty() {
+                return (text, Attributes::default());
+            }
+            attributes.id = Some(value.to_string());
+        } else if let Some(label) = attribute_value(token, "data-toc-label") {
+            attributes.label = Some(label.to_string());
+        } else if !(token.len() > 1 && token.starts_with('.')
+            || token == "-"
+            || token.find('=').is_some_and(|pos| pos > 0))
+        {
+            return (text, Attributes::default());
+        }
+    }
+
+    (before.trim_end_matches([' ', '\t']), attributes)
+}
+
+/// splits attributes at whitespace outside of quoted values. Returns `None` if a
+/// quote is not closed.
+fn attribute_tokens(text: &str) -> Option<Vec<&str>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c.is_whitespace() => {
+                if let Some(start) = start.take() {
+                    tokens.push(&text[start..i]);
+                }
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if let Some(start) = start {
+        tokens.push(&text[start..]);
+    }
+
+    Some(tokens)
+}
+
+/// returns the value of a `key=value` token without quotes
+fn attribute_value<'a>(token: &'a str, key: &str) -> Option<&'a str> {
+    let value = token.strip_prefix(key)?.strip_prefix('=')?;
+    let unquoted = ['"', '\'']
+        .into_iter()
+        .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote));
+
+    Some(unquoted.unwrap_or(value))
+}
+
+/// parses a `<!-- mktoc: label="Setup" -->` comment and returns the label
+fn label_comment(text: &str) -> Option<String> {
+    let options = text
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .trim()
+        .strip_prefix("mktoc:")?;
+
+    attribute_tokens(options)?
+        .into_iter()
+        .find_map(|token| attribute_value(token, "label"))
+        .map(str::to_string)
+}
+
+/// finds `<h1>` to `<h6>` elements in an HTML block. The inner text is
+/// returned without tags, an `id` attribute is used as explicit anchor.
+fn html_headings(html: &str) -> Vec<RawHeading> {
+    if !html.to_ascii_lowercase().contains("<h") {
+        return Vec::new();
+    }
+
+    let re_comment = Regex::new(r"(?s)<!--.*?(-->|$)").unwrap();
+    let re_heading = Regex::new(r"(?is)<h([1-6])(\s[^>]*)?>(.*?)</h([1-6])\s*>").unwrap();
+    let re_id =
+        Regex::new(r#"(?i)(?:^|\s)id\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+))"#).unwrap();
+    let re_label =
+        Regex::new(r#"(?i)(?:^|\s)data-toc-label\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+))"#)
+            .unwrap();
+    let re_tag = Regex::new(r"<[^>]*>").unwrap();
+
+    let html = re_comment.replace_all(html, "");
+    re_heading
+        .captures_iter(&html)
+        .filter(|caps| caps[1] == caps[4])
+        .map(|caps| {
+            let id = caps.get(2).and_then(|attributes| {
+                let id = re_id.captures(attributes.as_str())?;
+                let value = id.get(1).or(id.get(2)).or(id.get(3))?.as_str();
+                Some(value.to_string()).filter(|value| !value.is_empty())
+            });
+            let label = caps.get(2).and_then(|attributes| {
+                let label = re_label.captures(attributes.as_str())?;
+                let value = label.get(1).or(label.get(2)).or(label.get(3))?.as_str();
+                Some(value.to_string())
+            });
+            let text = re_tag.replace_all(&caps[3], "");
+
+            RawHeading {
+                level: caps[1].parse().unwrap(),
+                text: text.split_whitespace().collect::<Vec<&str>>().join(" "),
+                id,
+                ignored: false,
+                label,
+                offset: None,
+                span: 0..0,
+            }
+        })
+        .collect()
+}
+
+/// parses a Setext heading underline (`===` or `---`) and returns the level
+fn setext_underline(text: &str) -> Option<i32> {
+    let underline = text.trim_end_matches([' ', '\t']);
+    let marker = underline.chars().next()?;
+    let level = match marker {
+        '=' => 1,
+        '-' => 2,
+        _ => return None,
+    };
+
+    underline.chars().all(|c| c == marker).then_some(level)
+}
+
+/// parses an opening code fence and returns the fence character and length
+fn opening_fence(text: &str) -> Option<(char, usize)> {
+    let fence = text.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = text.chars().take_while(|c| *c == fence).count();
+    if len < 3 {
+        return None;
+    }
+
+    // the info string of a backtick fence may not contain backticks
+    if fence == '`' && text[len..].contains('`') {
+        return None;
+    }
+
+    Some((fence, len))
+}
+
+/// checks if the line closes a code block opened with `len` times `fence`
+fn is_closing_fence(text: &str, fence: char, len: usize) -> bool {
+    let count = text.chars().take_while(|c| *c == fence).count();
+    count >= len && is_blank(&text[count..])
+}
+
+/// checks if the line is a thematic break like `***`, `---` or `_ _ _`
+fn is_thematic_break(text: &str) -> bool {
+    let Some(marker) = text.chars().next().filter(|c| matches!(c, '*' | '-' | '_')) else {
+        return false;
+    };
+
+    let mut count = 0;
+    for c in text.chars() {
+        match c {
+            ' ' | '\t' => {}
+            c if c == marker => count += 1,
+            _ => return false,
+        }
+    }
+
+    count >= 3
+}
+
+/// checks if the line starts an HTML block and returns its end condition.
+/// HTML blocks of kind 7 (any complete tag) can't interrupt a paragraph.
+fn html_block_start(text: &str, in_paragraph: bool) -> Option<HtmlEnd> {
+    if !text.starts_with('<') {
+        return None;
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let after_tag = |name_end: usize| {
+        lower[name_end..].is_empty()
+            || lower[name_end..].starts_with([' ', '\t', '>'])
+            || lower[name_end..].starts_with("/>")
+    };
+
+    for tag in ["script", "pre", "style", "textarea"] {
+        if lower[1..].starts_with(tag) && after_tag(tag.len() + 1) {
+            return Some(HtmlEnd::RawText);
+        }
+    }
+
+    if lower.starts_with("<!--") {
+        return Some(HtmlEnd::Comment);
+    }
+    if lower.starts_with("<?") {
+        return Some(HtmlEnd::ProcessingInstruction);
+    }
+    if lower.starts_with("<![cdata[") {
+        return Some(HtmlEnd::CData);
+    }
+    if lower[1..].starts_with('!') && lower[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Some(HtmlEnd::Declaration);
+    }
+
+    let name_start = if lower.starts_with("</") { 2 } else { 1 };
+    let name_len = lower[name_start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+    let name = &lower[name_start..name_start + name_len];
+    if BLOCK_TAGS.contains(&name) && after_tag(name_start + name_len) {
+        return Some(HtmlEnd::BlankLine);
+    }
+
+    if !in_paragraph
+        && let Some(rest) = complete_tag(text)
+        && is_blank(rest)
+    {
+        return Some(HtmlEnd::BlankLine);
+    }
+
+    None
+}
+
+/// parses a complete open or closing tag and returns the text after it
+pub(crate) fn complete_tag(text: &str) -> Option<&str> {
+    let closing = text.starts_with("</");
+    let mut rest = if closing { &text[2..] } else { &text[1..] };
+
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    rest = rest.trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '-');
+
+    if closing {
+        return rest.trim_start().strip_prefix('>');
+    }
+
+    loop {
+        let trimmed = rest.trim_start();
+        let had_space = trimmed.len() < rest.len();
+        rest = trimmed;
+
+        if let Some(after) = rest.strip_prefix("/>").or_else(|| rest.strip_prefix('>')) {
+            return Some(after);
+        }
+
+        // every attribute has to be separated by whitespace
+        if !had_space
+            || !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        {
+            return None;
+        }
+        rest = rest.trim_start_matches(|c: char| {
+            c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-')
+        });
+
+        // optional attribute value
+        let trimmed = rest.trim_start();
+        if let Some(value) = trimmed.strip_prefix('=') {
+            let value = value.trim_start();
+            rest = if let Some(quoted) = value.strip_prefix('"') {
+                &quoted[quoted.find('"')? + 1..]
+            } else if let Some(quoted) = value.strip_prefix('\'') {
+                &quoted[quoted.find('\'')? + 1..]
+            } else {
+                let end = value
+                    .find(|c: char| c.is_whitespace() || "\"'=<>`".contains(c))
+                    .unwrap_or(value.len());
+                if end == 0 {
+                    return None;
+                }
+                &value[end..]
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels_and_text(content: &str) -> Vec<(i32, String)> {
+        scan(content)
+            .headings
+            .into_iter()
+            .map(|h| (h.level, h.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_atx_headings() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "all levels",
+                input: "# 1\n## 2\n### 3\n#### 4\n##### 5\n###### 6",
+                expected: vec![(1, "1"), (2, "2"), (3, "3"), (4, "4"), (5, "5"), (6, "6")],
+            },
+            TestCase {
+                name: "more than 6 characters is not a heading",
+                input: "####### foo",
+                expected: vec![],
+            },
+            TestCase {
+                name: "space is required after the opening sequence",
+                input: "#5 bolt\n#hashtag",
+                expected: vec![],
+            },
+            TestCase {
+                name: "up to 3 spaces indentation",
+                input: " # one\n  ## two\n   ### three\n    #### code",
+                expected: vec![(1, "one"), (2, "two"), (3, "three")],
+            },
+            TestCase {
+                name: "closing sequence is removed",
+                input: "## Foo ##\n### Bar ###########\n# Baz#\n## ###",
+                expected: vec![(2, "Foo"), (3, "Bar"), (1, "Baz#"), (2, "")],
+            },
+            TestCase {
+                name: "whitespace around content is removed",
+                input: "#   Foo   \n#\tTab\t",
+                expected: vec![(1, "Foo"), (1, "Tab")],
+            },
+            TestCase {
+                name: "empty headings",
+                input: "#\n## #",
+                expected: vec![(1, ""), (2, "")],
+            },
+            TestCase {
+                name: "headings can interrupt paragraphs",
+                input: "Foo bar\n# baz\nBar foo",
+                expected: vec![(1, "baz")],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+
+    #[test]
+    fn test_setext_headings() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "both levels",
+                input: "Foo\n===\n\nBar\n---",
+                expected: vec![(1, "Foo"), (2, "Bar")],
+            },
+            TestCase {
+                name: "underline may be indented and have trailing spaces",
+                input: "Foo\n   ====   ",
+                expected: vec![(1, "Foo")],
+            },
+            TestCase {
+                name: "multi line content is joined",
+                input: "Foo\n  bar\n---",
+                expected: vec![(2, "Foo bar")],
+            },
+            TestCase {
+                name: "thematic break without paragraph",
+                input: "---\n\n---\n***",
+                expected: vec![],
+            },
+            TestCase {
+                name: "underline with spaces is a thematic break",
+                input: "Foo\n- - -\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "indented underline continues the paragraph",
+                input: "Foo\n    ---",
+                expected: vec![],
+            },
+            TestCase {
+                name: "lazy continuation can't be an underline",
+                input: "> Foo\n===",
+                expected: vec![],
+            },
+            TestCase {
+                name: "underline after a code block",
+                input: "    code\n---\n```\nFoo\n```\n---",
+                expected: vec![],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+
+    #[test]
+    fn test_front_matter() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "YAML front matter",
+                input: "---\ntitle: Foo\n# draft settings\ndraft: true\n---\n\nBar\n===",
+                expected: vec![(1, "Bar")],
+            },
+            TestCase {
+                name: "YAML front matter closed with dots",
+                input: "---\n# comment\n...\n# Bar",
+                expected: vec![(1, "Bar")],
+            },
+            TestCase {
+                name: "TOML front matter",
+                input: "+++\n# comment\ntitle = \"Foo\"\n+++\n# Bar",
+                expected: vec![(1, "Bar")],
+            },
+            TestCase {
+                name: "JSON front matter",
+                input: ";;;\n{\"title\": \"Foo\"}\n;;;\n# Bar",
+                expected: vec![(1, "Bar")],
+            },
+            TestCase {
+                name: "CRLF line endings",
+                input: "---\r\n# comment\r\n---\r\n# Bar\r\n",
+                expected: vec![(1, "Bar")],
+            },
+            TestCase {
+                name: "unclosed front matter is Markdown",
+                input: "+++\n# Foo",
+                expected: vec![(1, "Foo")],
+            },
+            TestCase {
+                name: "front matter only at the start",
+                input: "Foo\n\n---\ntitle: Foo\n---",
+                expected: vec![(2, "title: Foo")],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+
+    #[test]
+    fn test_heading_attributes() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(&'a str, Option<&'a str>)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "explicit id",
+                input: "## Installation {#install}",
+                expected: vec![("Installation", Some("install"))],
+            },
+            TestCase {
+                name: "id with classes and closing sequence",
+                input: "## Installation {.big #install data-x=1} ##",
+                expected: vec![("Installation", Some("install"))],
+            },
+            TestCase {
+                name: "attributes without id are removed",
+                input: "# Appendix {.unnumbered}\n# Index {-}",
+                expected: vec![("Appendix", None), ("Index", None)],
+            },
+            TestCase {
+                name: "quoted values with spaces",
+                input: "## Installation {#install data-toc-label=\"Quick setup\" title='A B'}",
+                expected: vec![("Installation", Some("install"))],
+            },
+            TestCase {
+                name: "Setext heading",
+                input: "Installation {#install}\n---",
+                expected: vec![("Installation", Some("install"))],
+            },
+            TestCase {
+                name: "braces that are not attributes are kept",
+                input: "# Use {foo}\n# Empty {}\n# Hash {#}\n# Not {#at} the end",
+                expected: vec![
+                    ("Use {foo}", None),
+                    ("Empty {}", None),
+                    ("Hash {#}", None),
+                    ("Not {#at} the end", None),
+                ],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let headings: Vec<(String, Option<String>)> = scan(test.input)
+                .headings
+                .into_iter()
+                .map(|h| (h.text, h.id))
+                .collect();
+            let expected: Vec<(String, Option<String>)> = test
+                .expected
+                .into_iter()
+                .map(|(text, id)| (text.to_string(), id.map(str::to_string)))
+                .collect();
+            assert_eq!(headings, expected);
+        }
+    }
+
+    #[test]
+    fn test_heading_labels() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(&'a str, Option<&'a str>)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "label comment before a heading",
+                input: "<!-- mktoc: label=\"Setup\" -->\n## How to set up the project\n## Usage",
+                expected: vec![
+                    ("How to set up the project", Some("Setup")),
+                    ("Usage", None),
+                ],
+            },
+            TestCase {
+                name: "label comment before Setext and HTML headings",
+                input: "<!-- mktoc: label='Foo' -->\nLong foo\n---\n\n<!--mktoc: label=Bar-->\n<h2>Long bar</h2>",
+                expected: vec![("Long foo", Some("Foo")), ("Long bar", Some("Bar"))],
+            },
+            TestCase {
+                name: "label comment has to be on the previous line",
+                input: "<!-- mktoc: label=\"Setup\" -->\n\n## Setup",
+                expected: vec![("Setup", None)],
+            },
+            TestCase {
+                name: "label attributes",
+                input: "## Long foo {data-toc-label=\"Foo\"}\n<h2 data-toc-label=\"Bar\">Long bar</h2>",
+                expected: vec![("Long foo", Some("Foo")), ("Long bar", Some("Bar"))],
+            },
+            TestCase {
+                name: "attribute takes priority over the comment",
+                input: "<!-- mktoc: label=\"Foo\" -->\n## Long foo {data-toc-label=\"Bar\"}",
+                expected: vec![("Long foo", Some("Bar"))],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let headings = scan(test.input).headings;
+            assert_eq!(
+                headings
+                    .iter()
+                    .map(|h| (h.text.as_str(), h.label.as_deref()))
+                    .collect::<Vec<_>>(),
+                test.expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "backtick fence",
+                input: "```\n# comment\n```\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "tilde fence",
+                input: "~~~ sh\n# comment\n~~~\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "closing fence must match the opening character",
+                input: "```\n~~~\n# comment\n```\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "closing fence must be at least as long",
+                input: "````md\n```\n# comment\n```\n````\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "unclosed fence runs until the end",
+                input: "```\n# comment",
+                expected: vec![],
+            },
+            TestCase {
+                name: "indented code block",
+                input: "    # comment\n\n    # more\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "indented line continues a paragraph",
+                input: "text\n    # not code\n# after",
+                expected: vec![(1, "after")],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+
+    #[test]
+    fn test_list_items() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "heading in list item",
+                input: "- # Foo\n1. ## Bar",
+                expected: vec![(1, "Foo"), (2, "Bar")],
+            },
+            TestCase {
+                name: "fenced code in list item",
+                input: "- item\n\n  ```\n  # comment\n  ```\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "fenced code in ordered list item",
+                input: "1. item\n   ~~~\n   # comment\n\n   # comment\n   ~~~\n   # nested",
+                expected: vec![(1, "nested")],
+            },
+            TestCase {
+                name: "fence is closed with the list item",
+                input: "- ```\n  # comment\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "indented code in list item",
+                input: "- item\n\n      # comment\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "nested lists",
+                input: "- a\n  - b\n    ```\n    # comment\n    ```\n    # nested",
+                expected: vec![(1, "nested")],
+            },
+            TestCase {
+                name: "thematic break is not a list item",
+                input: "* * *\n    # code",
+                expected: vec![],
+            },
+            TestCase {
+                name: "ordered list not starting at 1 can't interrupt a paragraph",
+                input: "text\n2. ```\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "empty list item ends at a second blank line",
+                input: "-\n\n  # not in the item",
+                expected: vec![(1, "not in the item")],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+
+    #[test]
+    fn test_html_blocks() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "comment",
+                input: "<!--\n# hidden\n-->\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "single line comment",
+                input: "<!-- comment -->\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "block tag ends at blank line",
+                input: "<div>\n# hidden\n\n# after\n</div>",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "pre ends at closing tag",
+                input: "<pre>\n# hidden\n\n# hidden\n</pre>\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "any complete tag starts a block",
+                input: "<custom-element attr=\"x\">\n# hidden\n\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "inline tag in paragraph is not a block",
+                input: "<span>text</span> more\n# after",
+                expected: vec![(1, "after")],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+
+    #[test]
+    fn test_html_headings() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str, Option<&'a str>)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "heading without id",
+                input: "<h1 align=\"center\">mktoc</h1>",
+                expected: vec![(1, "mktoc", None)],
+            },
+            TestCase {
+                name: "heading with id",
+                input: "<h2 id=\"install\">Install</h2>\n<H3 class='x' ID='usage'>Usage</H3>",
+                expected: vec![(2, "Install", Some("install")), (3, "Usage", Some("usage"))],
+            },
+            TestCase {
+                name: "nested tags are stripped",
+                input: "<h2>\n  <img src=\"logo.png\">\n  <b>Bold</b> title\n</h2>",
+                expected: vec![(2, "Bold title", None)],
+            },
+            TestCase {
+                name: "data attributes are not ids",
+                input: "<h2 data-id=\"x\">Foo</h2>",
+                expected: vec![(2, "Foo", None)],
+            },
+            TestCase {
+                name: "mismatched closing tag",
+                input: "<h2>Foo</h3>",
+                expected: vec![],
+            },
+            TestCase {
+                name: "document order with Markdown headings",
+                input: "# One\n\n<div>\n<h2>Two</h2>\n</div>\n\n# Three",
+                expected: vec![(1, "One", None), (2, "Two", None), (1, "Three", None)],
+            },
+            TestCase {
+                name: "not in code blocks or comments",
+                input: "```\n<h2>Foo</h2>\n```\n<!--\n<h2>Bar</h2>\n-->",
+                expected: vec![],
+            },
+            TestCase {
+                name: "block ends with its container",
+                input: "> <h2>Foo</h2>\n# Bar",
+                expected: vec![(2, "Foo", None), (1, "Bar", None)],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let headings: Vec<(i32, String, Option<String>)> = scan(test.input)
+                .headings
+                .into_iter()
+                .map(|h| (h.level, h.text, h.id))
+                .collect();
+            let expected: Vec<(i32, String, Option<String>)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text, id)| (level, text.to_string(), id.map(str::to_string)))
+                .collect();
+            assert_eq!(headings, expected);
+        }
+    }
+
+    #[test]
+    fn test_html_lines() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<&'a str>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "comments on separate lines",
+                input: "<!-- BEGIN -->\n- list\n<!-- END -->",
+                expected: vec!["<!-- BEGIN -->", "<!-- END -->"],
+            },
+            TestCase {
+                name: "multi line comment",
+                input: "text\n\n  <!--\n  # hidden\n-->\ntext",
+                expected: vec!["<!--", "# hidden", "-->"],
+            },
+            TestCase {
+                name: "blank line ends the block",
+                input: "<details>\n<summary>Foo</summary>\n\n# Bar\n</details>\n",
+                expected: vec!["<details>", "<summary>Foo</summary>", "</details>"],
+            },
+            TestCase {
+                name: "no HTML in code blocks",
+                input: "```html\n<!-- BEGIN -->\n```\n    <!-- END -->",
+                expected: vec![],
+            },
+            TestCase {
+                name: "container markers are not included",
+                input: "> <!-- comment -->\n- <div>\n  </div>",
+                expected: vec!["<!-- comment -->", "<div>", "</div>"],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let lines: Vec<&str> = scan(test.input)
+                .html_lines
+                .into_iter()
+                .map(|range| &test.input[range])
+                .collect();
+            assert_eq!(lines, test.expected);
+        }
+    }
+
+    #[test]
+    fn test_heading_spans() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<&'a str>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "ATX headings",
+                input: "# Foo\r\ntext\n  ## Bar ##\n",
+                expected: vec!["# Foo", "  ## Bar ##"],
+            },
+            TestCase {
+                name: "Setext headings without link definitions",
+                input: "[foo]: /url\nFoo\n  bar\n---\n> Baz\n> ===",
+                expected: vec!["Foo\n  bar\n---", "> Baz\n> ==="],
+            },
+            TestCase {
+                name: "HTML headings span their block",
+                input: "text\n\n<div>\n<h2>Foo</h2>\n</div>\n\ntext",
+                expected: vec!["<div>\n<h2>Foo</h2>\n</div>"],
+            },
+            TestCase {
+                name: "headings in list items",
+                input: "- # Foo\n\n  Bar\n  ---",
+                expected: vec!["- # Foo", "  Bar\n  ---"],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let spans: Vec<&str> = scan(test.input)
+                .headings
+                .into_iter()
+                .map(|heading| &test.input[heading.span])
+                .collect();
+            assert_eq!(spans, test.expected);
+        }
+    }
+
+    #[test]
+    fn test_ignore_markers() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(&'a str, bool)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "marker on the line before a heading",
+                input: "<!-- mktoc-ignore -->\n# Foo\n# Bar",
+                expected: vec![("Foo", true), ("Bar", false)],
+            },
+            TestCase {
+                name: "marker before Setext and HTML headings",
+                input: "<!-- mktoc-ignore -->\nFoo\n===\n\n<!-- mktoc-ignore -->\n<h2>Bar</h2>",
+                expected: vec![("Foo", true), ("Bar", true)],
+            },
+            TestCase {
+                name: "marker has to be on the previous line",
+                input: "<!-- mktoc-ignore -->\n\n# Foo",
+                expected: vec![("Foo", false)],
+            },
+            TestCase {
+                name: "ignored region",
+                input: "# Foo\n<!-- mktoc-ignore-start -->\n# Bar\n\n<h2>Baz</h2>\n\n<!-- mktoc-ignore-end -->\n# Qux",
+                expected: vec![("Foo", false), ("Bar", true), ("Baz", true), ("Qux", false)],
+            },
+            TestCase {
+                name: "markers in code blocks don't count",
+                input: "```\n<!-- mktoc-ignore-start -->\n```\n# Foo",
+                expected: vec![("Foo", false)],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let headings = scan(test.input).headings;
+            assert_eq!(
+                headings
+                    .iter()
+                    .map(|h| (h.text.as_str(), h.ignored))
+                    .collect::<Vec<_>>(),
+                test.expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_link_definitions() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<&'a str>,
+            headings: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "definitions at the start of paragraphs",
+                input: "[Foo]: /url\n[bar  Baz]: <my url> \"title\"\n\n> [quoted]: https://example.com",
+                expected: vec!["bar baz", "foo", "quoted"],
+                headings: vec![],
+            },
+            TestCase {
+                name: "no definitions inside paragraphs or code",
+                input: "text\n[foo]: /url\n\n    [bar]: /url\n```\n[baz]: /url\n```",
+                expected: vec![],
+                headings: vec![],
+            },
+            TestCase {
+                name: "invalid definitions",
+                input: "[foo]:\n\n[bar] : /url\n\n[[baz]]: /url",
+                expected: vec![],
+                headings: vec![],
+            },
+            TestCase {
+                name: "definitions are not part of Setext headings",
+                input: "[foo]: /url\nHeading\n===\n\n[bar]: /url\n---",
+                expected: vec!["bar", "foo"],
+                headings: vec![(1, "Heading")],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let document = scan(test.input);
+            let mut labels: Vec<&str> = document
+                .link_definitions
+                .iter()
+                .map(String::as_str)
+                .collect();
+            labels.sort();
+            assert_eq!(labels, test.expected);
+            assert_eq!(
+                document
+                    .headings
+                    .iter()
+                    .map(|h| (h.level, h.text.as_str()))
+                    .collect::<Vec<_>>(),
+                test.headings
+            );
+        }
+    }
+
+    #[test]
+    fn test_blockquotes() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Vec<(i32, &'a str)>,
+        }
+
+        let tests = [
+            TestCase {
+                name: "heading in block quote",
+                input: "> # Foo\n> bar",
+                expected: vec![(1, "Foo")],
+            },
+            TestCase {
+                name: "nested block quotes",
+                input: "> > ## Foo",
+                expected: vec![(2, "Foo")],
+            },
+            TestCase {
+                name: "fenced code in block quote",
+                input: "> ```\n> # comment\n> ```\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "fence is closed with the block quote",
+                input: "> ```\n# after",
+                expected: vec![(1, "after")],
+            },
+            TestCase {
+                name: "indented code in block quote",
+                input: ">     # comment",
+                expected: vec![],
+            },
+        ];
+
+        for test in tests {
+            dbg!(test.name);
+            let expected: Vec<(i32, String)> = test
+                .expected
+                .into_iter()
+                .map(|(level, text)| (level, text.to_string()))
+                .collect();
+            assert_eq!(levels_and_text(test.input), expected);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+//! Rules to turn heading text into anchors ("slugs").
+//!
+//! Every platform rendering Markdown has its own idea of how an anchor should
+//! look, so the style is configurable with `slug_style` in the [`Config`](crate::Config).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The platform whose anchor rules are used for the ToC links
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SlugStyle {
+    #[default]
+    #[serde(rename = "github")]
+    GitHub,
+    #[serde(rename = "gitlab")]
+    GitLab,
+    #[serde(rename = "bitbucket")]
+    Bitbucket,
+    #[serde(rename = "gitea")]
+    Gitea,
+    #[serde(rename = "azure-devops", alias = "azuredevops")]
+    AzureDevOps,
+    #[serde(rename = "mdbook")]
+    MdBook,
+    #[serde(rename = "hugo")]
+    Hugo,
+    #[serde(rename = "pandoc")]
+    Pandoc,
+}
+
+impl SlugStyle {
+    /// all available styles
+    pub const ALL: [SlugStyle; 8] = [
+        SlugStyle::GitHub,
+        SlugStyle::GitLab,
+        SlugStyle::Bitbucket,
+        SlugStyle::Gitea,
+        SlugStyle::AzureDevOps,
+        SlugStyle::MdBook,
+        SlugStyle::Hugo,
+        SlugStyle::Pandoc,
+    ];
+
+    /// the name used in the config and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlugStyle::GitHub => "github",
+            SlugStyle::GitLab => "gitlab",
+            SlugStyle::Bitbucket => "bitbucket",
+            SlugStyle::Gitea => "gitea",
+            SlugStyle::AzureDevOps => "azure-devops",
+            SlugStyle::MdBook => "mdbook",
+            SlugStyle::Hugo => "hugo",
+            SlugStyle::Pandoc => "pandoc",
+        }
+    }
+
+    /// converts the plain heading text to an anchor
+    pub fn slug(&self, text: &str) -> String {
+        match self {
+            SlugStyle::GitHub => crate::text_to_url(text),
+            SlugStyle::GitLab => gitlab(text),
+            SlugStyle::Bitbucket => bitbucket(text),
+            SlugStyle::Gitea => gitea(text),
+            SlugStyle::AzureDevOps => azure_devops(text),
+            SlugStyle::MdBook => mdbook(text),
+            SlugStyle::Hugo => hugo(text),
+            SlugStyle::Pandoc => pandoc(text),
+        }
+    }
+
+    /// returns the anchor for the `count`th repetition of a slug
+    pub fn duplicate(&self, slug: &str, count: i64) -> String {
+        match self {
+            // Python-Markdown, which Bitbucket uses, appends an underscore
+            SlugStyle::Bitbucket => format!("{}_{}", slug, count),
+            _ => format!("{}-{}", slug, count),
+        }
+    }
+}
+
+impl fmt::Display for SlugStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SlugStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        if name == "azuredevops" {
+            return Ok(SlugStyle::AzureDevOps);
+        }
+
+        SlugStyle::ALL
+            .into_iter()
+            .find(|style| style.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = SlugStyle::ALL.iter().map(SlugStyle::name).collect();
+                format!(
+                    "unknown slug style '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// GitLab keeps word characters, hyphens and spaces, then squeezes hyphens.
+/// Anchors consisting of digits only are prefixed with `anchor-`.
+fn gitlab(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        let c = match c {
+            ' ' => '-',
+            c if c.is_alphanumeric() || c == '_' || c == '-' => c,
+            _ => continue,
+        };
+        if !(c == '-' && slug.ends_with('-')) {
+            slug.push(c);
+        }
+    }
+
+    if !slug.is_empty() && slug.chars().all(|c| c.is_ascii_digit()) {
+        slug.insert_str(0, "anchor-");
+    }
+
+    slug
+}
+
+/// Bitbucket uses the `slugify` function of Python-Markdown: accents are
+/// removed, all other non-ASCII characters are dropped. Anchors are prefixed
+/// with `markdown-header-`.
+fn bitbucket(text: &str) -> String {
+    let ascii: String = text
+        .chars()
+        .filter_map(|c| {
+            if c.is_ascii() {
+                Some(c)
+            } else {
+                fold_latin1(c)
+            }
+        })
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-') || c.is_whitespace())
+        .collect();
+
+    let mut slug = String::from("markdown-header-");
+    for c in ascii.trim().to_lowercase().chars() {
+        if c == '-' || c.is_whitespace() {
+            if !slug.ends_with('-') {
+                slug.push('-');
+            }
+        } else {
+            slug.push(c);
+        }
+    }
+
+    slug
+}
+
+/// Gitea keeps letters, numbers and underscores. Everything else separates
+/// words, which are joined with a single hyphen.
+fn gitea(text: &str) -> String {
+    let mut slug = String::new();
+    let mut needs_dash = false;
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            if needs_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            needs_dash = false;
+            slug.extend(c.to_lowercase());
+        } else {
+            needs_dash = true;
+        }
+    }
+
+    slug
+}
+
+/// Azure DevOps wikis keep punctuation in anchors, so everything that is not
+/// alphanumeric or one of `-_.~` is percent-encoded.
+fn azure_devops(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        match c {
+            ' ' => slug.push('-'),
+            c if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '~') => slug.push(c),
+            c => {
+                let mut buf = [0; 4];
+                for byte in c.encode_utf8(&mut buf).bytes() {
+                    slug.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+
+    slug
+}
+
+/// mdBook keeps alphanumeric characters, `_` and `-`, turns whitespace into
+/// hyphens and only lowercases ASCII characters.
+fn mdbook(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_ascii_lowercase())
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Hugo's default `github` heading ids keep letters, digits and underscores,
+/// spaces and hyphens become hyphens.
+fn hugo(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        match c {
+            ' ' | '-' => slug.push('-'),
+            c if c.is_alphanumeric() || c == '_' => slug.extend(c.to_lowercase()),
+            _ => {}
+        }
+    }
+
+    slug
+}
+
+/// Pandoc keeps alphanumerics, `_`, `-` and `.`, turns whitespace into hyphens
+/// and removes everything before the first letter. Empty ids become `section`.
+fn pandoc(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if slug.is_empty() && !c.is_alphabetic() {
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => slug.push('-'),
+            c if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') => {
+                slug.extend(c.to_lowercase())
+            }
+            _ => {}
+        }
+    }
+
+    if slug.is_empty() {
+        slug.push_str("section");
+    }
+
+    slug
+}
+
+/// removes the accent from Latin-1 letters, like an NFKD normalization would
+fn fold_latin1(c: char) -> Option<char> {
+    const ACCENTED: &str = "ÀÁÂÃÄÅÇÈÉÊËÌÍÎÏÑÒÓÔÕÖÙÚÛÜÝàáâãäåçèéêëìíîïñòóôõöùúûüýÿ";
+    const BASE: &str = "AAAAAACEEEEIIIINOOOOOUUUUYaaaaaaceeeeiiiinooooouuuuyy";
+
+    let index = ACCENTED.chars().position(|accented| accented == c)?;
+    BASE.chars().nth(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug_corpus() {
+        struct TestCase<'a> {
+            input: &'a str,
+            // expected anchors in the order of `SlugStyle::ALL`
+            expected: [&'a str; 8],
+        }
+
+        let tests = [
+            TestCase {
+                input: "Hello World",
+                expected: [
+                    "hello-world",
+                    "hello-world",
+                    "markdown-header-hello-world",
+                    "hello-world",
+                    "hello-world",
+                    "hello-world",
+                    "hello-world",
+                    "hello-world",
+                ],
+            },
+            TestCase {
+                input: "Hello, World!",
+                expected: [
+                    "hello-world",
+                    "hello-world",
+                    "markdown-header-hello-world",
+                    "hello-world",
+                    "hello%2C-world%21",
+                    "hello-world",
+                    "hello-world",
+                    "hello-world",
+                ],
+            },
+            TestCase {
+                input: "`mktoc` usage",
+                expected: [
+                    "mktoc-usage",
+                    "mktoc-usage",
+                    "markdown-header-mktoc-usage",
+                    "mktoc-usage",
+                    "%60mktoc%60-usage",
+                    "mktoc-usage",
+                    "mktoc-usage",
+                    "mktoc-usage",
+                ],
+            },
+            TestCase {
+                input: "Über uns",
+                expected: [
+                    "über-uns",
+                    "über-uns",
+                    "markdown-header-uber-uns",
+                    "über-uns",
+                    "über-uns",
+                    "Über-uns",
+                    "über-uns",
+                    "über-uns",
+                ],
+            },
+            TestCase {
+                input: "1. Introduction",
+                expected: [
+                    "1-introduction",
+                    "1-introduction",
+                    "markdown-header-1-introduction",
+                    "1-introduction",
+                    "1.-introduction",
+                    "1-introduction",
+                    "1-introduction",
+                    "introduction",
+                ],
+            },
+            TestCase {
+                input: "C# & .NET",
+                expected: [
+                    "c--net",
+                    "c-net",
+                    "markdown-header-c-net",
+                    "c-net",
+                    "c%23-%26-.net",
+                    "c--net",
+                    "c--net",
+                    "c--.net",
+                ],
+            },
+            TestCase {
+                input: "snake_case and kebab-case",
+                expected: [
+                    "snake_case-and-kebab-case",
+                    "snake_case-and-kebab-case",
+                    "markdown-header-snake_case-and-kebab-case",
+                    "snake_case-and-kebab-case",
+                    "snake_case-and-kebab-case",
+                    "snake_case-and-kebab-case",
+                    "snake_case-and-kebab-case",
+                    "snake_case-and-kebab-case",
+                ],
+            },
+            TestCase {
+                input: "Emoji 💃",
+                expected: [
+                    "emoji-",
+                    "emoji-",
+                    "markdown-header-emoji",
+                    "emoji",
+                    "emoji-%F0%9F%92%83",
+                    "emoji-",
+                    "emoji-",
+                    "emoji-",
+                ],
+            },
+            TestCase {
+                input: "404",
+                expected: [
+                    "404",
+                    "anchor-404",
+                    "markdown-header-404",
+                    "404",
+                    "404",
+                    "404",
+                    "404",
+                    "section",
+                ],
+            },
+        ];
+
+        for test in tests {
+            for (style, expected) in SlugStyle::ALL.iter().zip(test.expected) {
+                dbg!(test.input, style);
+                assert_eq!(style.slug(test.input), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_duplicate() {
+        assert_eq!(SlugStyle::GitHub.duplicate("foo", 1), "foo-1");
+        assert_eq!(SlugStyle::GitLab.duplicate("foo", 2), "foo-2");
+        assert_eq!(
+            SlugStyle::Bitbucket.duplicate("markdown-header-foo", 1),
+            "markdown-header-foo_1"
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        for style in SlugStyle::ALL {
+            assert_eq!(style.name().parse::<SlugStyle>(), Ok(style));
+        }
+        assert_eq!("GitLab".parse::<SlugStyle>(), Ok(SlugStyle::GitLab));
+        assert_eq!(
+            "azuredevops".parse::<SlugStyle>(),
+            Ok(SlugStyle::AzureDevOps)
+        );
+        assert!("sourcehut".parse::<SlugStyle>().is_err());
+    }
+}
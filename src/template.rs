@@ -0,0 +1,187 @@
+//! Templates the ToC is rendered with.
+//!
+//! A template consists of a header, a line per entry and a footer. The entry line
+//! contains variables in braces like `{text}` or `{anchor}` that are replaced with
+//! the values of each heading, so the ToC can be rendered as a table, with emoji
+//! bullets or as custom HTML instead of the default Markdown list.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The entry line of the default Markdown list
+pub const DEFAULT_ENTRY: &str = "{indent}{marker} [{text}](#{anchor})";
+
+/// Header, entry line and footer the ToC is rendered with
+///
+/// All parts may span multiple lines. Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Template {
+    /// written before the first entry, nothing by default
+    pub header: String,
+    /// written for every entry, with the variables replaced by its values
+    pub entry: String,
+    /// written after the last entry, nothing by default
+    pub footer: String,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            header: String::new(),
+            entry: DEFAULT_ENTRY.to_string(),
+            footer: String::new(),
+        }
+    }
+}
+
+impl Template {
+    /// parses a template file. The parts start with a `[header]`, `[entry]` or
+    /// `[footer]` line, text without any of those lines is used as entry line.
+    /// Without an entry line the one of the default Markdown list is used.
+    ///
+    /// ```
+    /// use mktoc::Template;
+    /// let template = Template::parse("[header]\n| Section |\n| --- |\n[entry]\n| [{text}](#{anchor}) |\n");
+    /// assert_eq!(template.header, "| Section |\n| --- |");
+    /// assert_eq!(template.entry, "| [{text}](#{anchor}) |");
+    /// assert_eq!(template.footer, "");
+    /// ```
+    pub fn parse(text: &str) -> Self {
+        let mut parts = [Vec::new(), Vec::new(), Vec::new()];
+        let mut part = 1;
+        for line in text.lines() {
+            match line.trim() {
+                "[header]" => part = 0,
+                "[entry]" => part = 1,
+                "[footer]" => part = 2,
+                _ => parts[part].push(line),
+            }
+        }
+
+        let [header, mut entry, footer] =
+            parts.map(|lines| lines.join("\n").trim_end_matches('\n').to_string());
+        if entry.trim().is_empty() {
+            entry = DEFAULT_ENTRY.to_string();
+        }
+
+        Self {
+            header,
+            entry,
+            footer,
+        }
+    }
+
+    /// reads and parses a template file, see [`Template::parse`]
+    pub fn from_file<P>(path: P) -> Result<Self, std::io::Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+}
+
+/// replaces the variables in the text with the values returned by `value`.
+/// Unknown variables are kept as they are.
+pub(crate) fn fill<F>(text: &str, value: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['{', '}']) {
+        filled.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            filled.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let variable = rest
+            .strip_prefix('{')
+            .and_then(|r| r.split_once('}'))
+            .and_then(|(name, after)| Some((value(name)?, after)));
+        match variable {
+            Some((replacement, after)) => {
+                filled.push_str(&replacement);
+                rest = after;
+            }
+            None => {
+                filled.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        struct TestCase<'a> {
+            name: &'a str,
+            input: &'a str,
+            expected: Template,
+        }
+        let test_cases = [
+            TestCase {
+                name: "Entry line only",
+                input: "{indent}- {text}\n",
+                expected: Template {
+                    header: String::new(),
+                    entry: String::from("{indent}- {text}"),
+                    footer: String::new(),
+                },
+            },
+            TestCase {
+                name: "All parts",
+                input: "[header]\n<table>\n[entry]\n<tr>\n  <td>{text}</td>\n</tr>\n\n[footer]\n</table>\n",
+                expected: Template {
+                    header: String::from("<table>"),
+                    entry: String::from("<tr>\n  <td>{text}</td>\n</tr>"),
+                    footer: String::from("</table>"),
+                },
+            },
+            TestCase {
+                name: "Parts in any order and default entry line",
+                input: "[footer]\n---\n[header]\n## Contents\n",
+                expected: Template {
+                    header: String::from("## Contents"),
+                    entry: String::from(DEFAULT_ENTRY),
+                    footer: String::from("---"),
+                },
+            },
+        ];
+
+        for test_case in test_cases {
+            assert_eq!(
+                Template::parse(test_case.input),
+                test_case.expected,
+                "{}",
+                test_case.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        let value = |name: &str| match name {
+            "text" => Some(String::from("Install")),
+            "level" => Some(String::from("2")),
+            _ => None,
+        };
+
+        assert_eq!(fill("{text} ({level})", value), "Install (2)");
+        assert_eq!(fill("{{text}} {{{text}}}", value), "{text} {Install}");
+        assert_eq!(fill("{unknown} {text", value), "{unknown} {text");
+        assert_eq!(fill("} {", value), "} {");
+        assert_eq!(fill("🦀 {text} ✨", value), "🦀 Install ✨");
+    }
+}